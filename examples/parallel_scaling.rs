@@ -0,0 +1,36 @@
+//! Times `below_parallel_on` against `below_vec` for a range of thread counts, to see how sieving scales with cores.
+//!
+//! ```sh
+//! cargo run --release --features rayon --example parallel_scaling [limit]
+//! ```
+//!
+//! The limit defaults to `10^9`. Thread counts past the number of cores available are still run,
+//! but can't be any faster than using every core.
+
+use std::time::Instant;
+
+fn main() {
+    let n = match std::env::args().nth(1) {
+        Some(arg) => arg.parse().expect("the limit should be a u64"),
+        None => 1_000_000_000,
+    };
+    let cores = std::thread::available_parallelism().map_or(1, usize::from);
+    println!("primes up to {n}, with {cores} cores available");
+
+    let now = Instant::now();
+    let expected = primes::below_vec(n);
+    let serial = now.elapsed().as_secs_f64();
+    println!("below_vec: {:.0}ms", serial * 1000.);
+
+    for threads in [1, 2, 4, 8, 16] {
+        let now = Instant::now();
+        let primes = primes::below_parallel_on(n, threads);
+        let time = now.elapsed().as_secs_f64();
+        assert_eq!(primes, expected, "below_parallel_on with {threads} threads");
+        println!(
+            "below_parallel_on, {threads} threads: {:.0}ms, {:.2}x below_vec",
+            time * 1000.,
+            serial / time,
+        );
+    }
+}
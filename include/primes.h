@@ -0,0 +1,49 @@
+/* C interface to the primes crate, built with `cargo rustc --lib --release --features ffi --crate-type cdylib`. */
+
+#ifndef PRIMES_H
+#define PRIMES_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Success. */
+#define PRIMES_OK 0
+/* No answer: there is no 0th prime, or an iterator is exhausted. */
+#define PRIMES_NONE 1
+/* A pointer that must not be null was null. */
+#define PRIMES_NULL (-1)
+/* The call panicked, which is a bug in the crate. */
+#define PRIMES_PANIC (-2)
+
+/* An iterator over primes, created by primes_iter_new_below. */
+typedef struct PrimesIter PrimesIter;
+
+/* Returns true if n is prime. */
+bool primes_is_prime(uint64_t n);
+
+/* Writes the nth prime, numbered from 1, to *out. */
+int primes_nth(uint64_t n, uint64_t *out);
+
+/* Writes up to cap of the primes less than or equal to limit to buf, and returns how many there are in total,
+   or SIZE_MAX if the call panicked. buf may be NULL if cap is 0. */
+size_t primes_below(uint64_t limit, uint64_t *buf, size_t cap);
+
+/* Returns a new iterator over the primes less than or equal to limit, or NULL if the call panicked. */
+PrimesIter *primes_iter_new_below(uint64_t limit);
+
+/* Writes the next prime from iter to *out, returning PRIMES_NONE once it is exhausted. */
+int primes_iter_next(PrimesIter *iter, uint64_t *out);
+
+/* Frees *iter and sets it to NULL, so that freeing it again does nothing. */
+void primes_iter_free(PrimesIter **iter);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
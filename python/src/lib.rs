@@ -0,0 +1,127 @@
+//! Bindings for calling the `primes` crate from Python with PyO3, as a module named `primes`.
+//!
+//! They are a crate of their own, rather than a feature of `primes`, so that pyo3 is never in its dependency graph.
+//! The module is built and installed into the current virtual environment, from this directory, with
+//!
+//! ```sh
+//! maturin develop --release
+//! ```
+//!
+//! or by hand with
+//!
+//! ```sh
+//! PYO3_BUILD_EXTENSION_MODULE=1 cargo build --release -p primes-python
+//! cp target/release/libprimes_python.so primes.so
+//! ```
+//!
+//! Every `u64` is a Python `int`, and one that doesn't fit, whether negative or from `2**64` up, raises `OverflowError`.
+//! Sieving and factorizing release the GIL, so other Python threads carry on meanwhile.
+//!
+//! ```python
+//! import primes
+//!
+//! assert primes.is_prime(2**64 - 59)
+//! assert primes.nth(100) == 541
+//! assert primes.below(30) == [2, 3, 5, 7, 11, 13, 17, 19, 23, 29]
+//! assert primes.factor(360) == [(2, 3), (3, 2), (5, 1)]
+//!
+//! # Lazily, for more primes than fit in a list.
+//! for p in primes.iter_below(2**64 - 1):
+//!     if p > 1000:
+//!         break
+//! ```
+//!
+//! The tests in `tests/python.rs` run the module in an embedded interpreter, with `cargo test -p primes-python`.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use primes::{first_vec, try_divisors, Builder, Primes};
+
+/// The most primes `first` and `below` return in a list, 10 million,
+/// which take about 400 MB as Python `int`s, past which they raise `ValueError`.
+/// Larger requests can be iterated lazily with `iter_first` and `iter_below` instead.
+pub const MAX_LIST_LEN: usize = 10_000_000;
+
+/// Returns whether `n` is prime.
+#[pyfunction]
+fn is_prime(py: Python<'_>, n: u64) -> bool {
+    py.detach(|| primes::is_prime(n))
+}
+
+/// Returns the `n`th prime, numbered from 1, or `None` for 0 or if it doesn't fit in 64 bits.
+#[pyfunction]
+fn nth(py: Python<'_>, n: u64) -> Option<u64> {
+    py.detach(|| primes::nth(n))
+}
+
+/// Returns a list of the first `n` primes, for `n` up to `MAX_LIST_LEN`.
+#[pyfunction]
+fn first(py: Python<'_>, n: u64) -> PyResult<Vec<u64>> {
+    if n > MAX_LIST_LEN as u64 {
+        return Err(too_long());
+    }
+    Ok(py.detach(|| first_vec(n)))
+}
+
+/// Returns a list of the primes less than or equal to `n`, if there are no more than `MAX_LIST_LEN` of them.
+#[pyfunction]
+fn below(py: Python<'_>, n: u64) -> PyResult<Vec<u64>> {
+    let primes: Vec<_> = py.detach(|| primes::below(n).take(MAX_LIST_LEN + 1).collect());
+    if primes.len() > MAX_LIST_LEN {
+        return Err(too_long());
+    }
+    Ok(primes)
+}
+
+fn too_long() -> PyErr {
+    PyValueError::new_err(format!(
+        "more than {MAX_LIST_LEN} primes asked for, which can be iterated with iter_first or iter_below instead"
+    ))
+}
+
+/// Returns an iterator over the first `n` primes, found a segment at a time as they are needed.
+#[pyfunction]
+fn iter_first(n: u64) -> PrimesIter {
+    PrimesIter(Builder::new().count(n).build())
+}
+
+/// Returns an iterator over the primes less than or equal to `n`, found a segment at a time as they are needed.
+#[pyfunction]
+fn iter_below(n: u64) -> PrimesIter {
+    PrimesIter(primes::below(n))
+}
+
+/// Returns the prime factorization of `n` as a list of `(prime, exponent)` tuples, with the primes in increasing order,
+/// or raises `ValueError` for 0.
+#[pyfunction]
+fn factor(py: Python<'_>, n: u64) -> PyResult<Vec<(u64, u64)>> {
+    let divisors = try_divisors(n).map_err(|error| PyValueError::new_err(error.to_string()))?;
+    Ok(py.detach(|| divisors.collect()))
+}
+
+/// An iterator over primes, which Python's `for` loops and `next` drive like a generator:
+/// once exhausted, it stays exhausted.
+#[pyclass(name = "Primes", module = "primes")]
+struct PrimesIter(Primes);
+
+#[pymethods]
+impl PrimesIter {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(mut slf: PyRefMut<'_, Self>) -> Option<u64> {
+        slf.0.next()
+    }
+}
+
+/// Primes, found with a segmented sieve of Eratosthenes.
+#[pymodule(name = "primes")]
+pub mod module {
+    #[pymodule_export]
+    use super::{below, factor, first, is_prime, iter_below, iter_first, nth, PrimesIter};
+
+    #[pymodule_export]
+    const MAX_LIST_LEN: usize = super::MAX_LIST_LEN;
+}
@@ -0,0 +1,114 @@
+//! The PyO3 bindings, run in an embedded interpreter with `cargo test -p primes-python`.
+
+use std::ffi::CStr;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+/// Runs `code` with the module imported as `primes`, panicking with the Python traceback if it raises.
+fn run(code: &CStr) {
+    Python::initialize();
+    Python::attach(|py| {
+        let globals = PyDict::new(py);
+        globals
+            .set_item("primes", pyo3::wrap_pymodule!(primes_python::module)(py))
+            .unwrap();
+        if let Err(error) = py.run(code, Some(&globals), None) {
+            error.display(py);
+            panic!("{error}");
+        }
+    });
+}
+
+#[test]
+fn is_prime_and_nth() {
+    run(cr#"
+assert primes.is_prime(2**64 - 59)
+assert not primes.is_prime(2**64 - 1)
+assert not primes.is_prime(0)
+assert primes.nth(1) == 2
+assert primes.nth(100) == 541
+assert primes.nth(0) is None
+assert primes.nth(2**64 - 1) is None
+"#);
+}
+
+#[test]
+fn lists_are_capped() {
+    run(cr#"
+assert primes.first(10) == [2, 3, 5, 7, 11, 13, 17, 19, 23, 29]
+assert primes.below(30) == [2, 3, 5, 7, 11, 13, 17, 19, 23, 29]
+assert primes.first(0) == [] and primes.below(1) == []
+assert len(primes.first(primes.MAX_LIST_LEN)) == primes.MAX_LIST_LEN
+
+for call in [lambda: primes.first(primes.MAX_LIST_LEN + 1), lambda: primes.below(2**64 - 1)]:
+    try:
+        call()
+    except ValueError as error:
+        assert "iter_first or iter_below" in str(error)
+    else:
+        raise AssertionError("no ValueError")
+"#);
+}
+
+#[test]
+fn factor() {
+    run(cr#"
+assert primes.factor(360) == [(2, 3), (3, 2), (5, 1)]
+assert primes.factor(1) == []
+assert primes.factor(2**63) == [(2, 63)]
+assert primes.factor(2**64 - 1) == [(3, 1), (5, 1), (17, 1), (257, 1), (641, 1), (65537, 1), (6700417, 1)]
+try:
+    primes.factor(0)
+except ValueError as error:
+    assert "0" in str(error)
+else:
+    raise AssertionError("no ValueError")
+"#);
+}
+
+#[test]
+fn out_of_range_ints_raise_overflow_error() {
+    run(cr#"
+for f in [primes.is_prime, primes.nth, primes.first, primes.below, primes.factor, primes.iter_first, primes.iter_below]:
+    for n in [2**64, -1, 10**30]:
+        try:
+            f(n)
+        except OverflowError:
+            pass
+        else:
+            raise AssertionError(f"no OverflowError from {f.__name__}({n})")
+"#);
+}
+
+#[test]
+fn iterators_behave_like_generators() {
+    run(cr#"
+it = primes.iter_below(30)
+assert iter(it) is it
+assert list(it) == [2, 3, 5, 7, 11, 13, 17, 19, 23, 29]
+for _ in range(3):
+    try:
+        next(it)
+    except StopIteration:
+        pass
+    else:
+        raise AssertionError("not exhausted")
+assert list(it) == []
+
+assert list(primes.iter_first(5)) == [2, 3, 5, 7, 11]
+assert next(primes.iter_first(0), None) is None
+assert type(primes.iter_first(5)).__name__ == "Primes"
+
+# Far more primes than fit in a list, taken lazily.
+it = primes.iter_below(2**64 - 1)
+taken = [next(it) for _ in range(100_000)]
+assert taken == primes.first(100_000)
+assert next(it) == primes.nth(100_001)
+big = primes.iter_below(2**64 - 1)
+for p in big:
+    if p > 1_000_000:
+        break
+assert p == 1_000_003
+"#);
+}
@@ -0,0 +1,223 @@
+//! Streaming primes to async code, with the sieving done on a thread of its own.
+
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec::{self, Vec};
+
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+
+use futures_core::Stream;
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread;
+
+use crate::Primes;
+
+/// The most segments of primes the worker gets ahead of the stream by.
+const BUFFERED: usize = 4;
+
+/// The segments of primes sieved but not yet taken by the stream, and how far each side has got.
+struct Channel {
+    batches: VecDeque<Vec<u64>>,
+    /// The waker of the task waiting for the next segment, if it is waiting.
+    waker: Option<Waker>,
+    /// Whether the worker has stopped, whether it ran out of primes or the stream was dropped.
+    finished: bool,
+    dropped: bool,
+}
+
+struct Shared {
+    channel: Mutex<Channel>,
+    /// Notified when the stream takes a segment or is dropped, for a worker waiting for room.
+    room: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Channel> {
+        self.channel.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Marks the worker finished when it stops, even by panicking, so that the stream never waits on it forever.
+struct Finish<'a>(&'a Shared);
+
+impl Drop for Finish<'_> {
+    fn drop(&mut self) {
+        let mut channel = self.0.lock();
+        channel.finished = true;
+        if let Some(waker) = channel.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// A [`Stream`] of primes, created by [`stream_below`] or [`Primes::into_stream`],
+/// which yields the same primes as the iterator it was created from.
+///
+/// Only available with the `async` feature.
+///
+/// The primes are sieved a segment at a time on a thread of its own, so the executor never sieves inline,
+/// and the thread gets at most a few segments ahead, so a slow consumer doesn't leave primes piling up in memory.
+/// Dropping the stream stops the thread once it finishes the segment it is on.
+pub struct PrimesStream {
+    shared: Arc<Shared>,
+    batch: vec::IntoIter<u64>,
+}
+
+impl PrimesStream {
+    fn new(mut primes: Primes) -> Self {
+        let shared = Arc::new(Shared {
+            channel: Mutex::new(Channel {
+                batches: VecDeque::with_capacity(BUFFERED),
+                waker: None,
+                finished: false,
+                dropped: false,
+            }),
+            room: Condvar::new(),
+        });
+        let worker = Arc::clone(&shared);
+        thread::spawn(move || {
+            let _finish = Finish(&worker);
+            let mut batch = Vec::new();
+            while primes.next_segment(&mut batch) {
+                let mut channel = worker.lock();
+                while channel.batches.len() >= BUFFERED && !channel.dropped {
+                    channel = worker
+                        .room
+                        .wait(channel)
+                        .unwrap_or_else(PoisonError::into_inner);
+                }
+                if channel.dropped {
+                    return;
+                }
+                channel.batches.push_back(core::mem::take(&mut batch));
+                if let Some(waker) = channel.waker.take() {
+                    waker.wake();
+                }
+            }
+        });
+        Self {
+            shared,
+            batch: Vec::new().into_iter(),
+        }
+    }
+
+    /// Returns a check of whether the worker has stopped, which still works once the stream is dropped.
+    ///
+    /// This is only for testing that dropping the stream stops the worker, and isn't part of the public API:
+    /// it is only built with the `test-hooks` feature, which the crate's own tests turn on.
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let stream = primes::stream_below(u64::MAX);
+    /// let finished = stream.worker_finished();
+    /// drop(stream);
+    /// let start = Instant::now();
+    /// while !finished() {
+    ///     assert!(start.elapsed() < Duration::from_secs(10), "the worker is still running");
+    ///     std::thread::sleep(Duration::from_millis(1));
+    /// }
+    /// ```
+    #[cfg(feature = "test-hooks")]
+    #[doc(hidden)]
+    pub fn worker_finished(&self) -> impl Fn() -> bool + Send + Sync + 'static {
+        let shared = Arc::clone(&self.shared);
+        move || shared.lock().finished
+    }
+}
+
+impl Stream for PrimesStream {
+    type Item = u64;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<u64>> {
+        loop {
+            if let Some(p) = self.batch.next() {
+                return Poll::Ready(Some(p));
+            }
+            let mut channel = self.shared.lock();
+            match channel.batches.pop_front() {
+                Some(batch) => {
+                    drop(channel);
+                    self.shared.room.notify_one();
+                    self.batch = batch.into_iter();
+                }
+                None if channel.finished => return Poll::Ready(None),
+                None => {
+                    channel.waker = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.batch.len(), None)
+    }
+}
+
+impl Drop for PrimesStream {
+    fn drop(&mut self) {
+        self.shared.lock().dropped = true;
+        self.shared.room.notify_one();
+    }
+}
+
+impl Primes {
+    /// Turns the iterator into a [`Stream`] of the primes it would have yielded, sieved on a thread of its own.
+    /// See [`PrimesStream`].
+    ///
+    /// Only available with the `async` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::StreamExt;
+    /// use primes::Builder;
+    ///
+    /// let mut primes = Builder::new().segment_size(64).limit(1_000_000).build();
+    /// primes.skip_to(900_000);
+    /// let streamed: Vec<_> = futures::executor::block_on(primes.into_stream().collect());
+    /// assert_eq!(streamed, primes::between(900_000, 1_000_000).collect::<Vec<_>>());
+    /// ```
+    pub fn into_stream(self) -> PrimesStream {
+        PrimesStream::new(self)
+    }
+}
+
+/// Returns a [`Stream`] of the primes less than or equal to `n`, sieved on a thread of its own,
+/// for async code that shouldn't block its executor. See [`PrimesStream`].
+///
+/// Only available with the `async` feature.
+///
+/// # Examples
+///
+/// ```
+/// use futures::StreamExt;
+///
+/// let streamed: Vec<_> = futures::executor::block_on(primes::stream_below(10_000_000).collect());
+/// assert_eq!(streamed, primes::below_vec(10_000_000));
+///
+/// // On a current-thread runtime, which the sieving never blocks, taking a few primes at a time.
+/// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// runtime.block_on(async {
+///     let mut stream = primes::stream_below(u64::MAX);
+///     let ticker = tokio::spawn(async {
+///         for _ in 0..10 {
+///             tokio::task::yield_now().await;
+///         }
+///     });
+///     let first: Vec<_> = stream.by_ref().take(100_000).collect().await;
+///     assert!(first.into_iter().eq(primes::first(100_000)));
+///     assert_eq!(stream.next().await, primes::nth(100_001));
+///     ticker.await.unwrap();
+/// });
+///
+/// for n in [0, 1, 2, 100] {
+///     let streamed: Vec<_> = futures::executor::block_on(primes::stream_below(n).collect());
+///     assert_eq!(streamed, primes::below_vec(n));
+/// }
+/// ```
+pub fn stream_below(n: u64) -> PrimesStream {
+    crate::below(n).into_stream()
+}
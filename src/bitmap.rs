@@ -0,0 +1,241 @@
+//! A packed table of primality for every number below a limit.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::sieve::{as_index, Segments, WHEEL_PRIMES};
+
+/// Returns a bitmap recording whether each number less than `n` is prime.
+///
+/// Only odd numbers are stored, one bit each, so the bitmap takes roughly `n / 16` bytes.
+///
+/// # Panics
+///
+/// Panics if the bitmap has more words than a `usize` can count, which is possible on 32-bit targets.
+///
+/// # Examples
+///
+/// ```
+/// let sieve = primes::sieve_below(30);
+/// assert!(sieve.is_prime(29));
+/// assert!(!sieve.is_prime(27));
+/// assert_eq!(sieve.count(), 10);
+///
+/// let primes: Vec<_> = sieve.iter().collect();
+/// assert_eq!(&primes, &[2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+/// ```
+pub fn sieve_below(n: u64) -> PrimalityBitmap {
+    PrimalityBitmap::new(n)
+}
+
+/// The number of words in each block of the rank index, which is a cache line.
+const BLOCK: usize = 8;
+
+/// Primality of every number below a limit, packed one bit per odd number.
+///
+/// Alongside the bits, a rank index stores the number of primes before each block of 8 words,
+/// so that [`count_below`](PrimalityBitmap::count_below) only counts the bits within one block,
+/// and [`nth`](PrimalityBitmap::nth) binary searches the blocks before scanning one.
+/// That takes another `n / 128` bytes, on top of the `n / 16` for the bits.
+///
+/// Created by [`sieve_below`].
+pub struct PrimalityBitmap {
+    bits: Vec<u64>,
+    /// `ranks[b]` is the number of odd primes in the blocks before the `b`th, ending with the total.
+    ranks: Vec<u64>,
+    n: u64,
+}
+
+impl PrimalityBitmap {
+    fn new(n: u64) -> Self {
+        let words = usize::try_from((n / 2).div_ceil(64)).expect("too many numbers for a bitmap");
+        let mut bits = vec![0u64; words];
+        if n > 3 {
+            let mut segments = Segments::new(n - 1);
+            let mut set = |p: u64| bits[as_index(p / 128)] |= 1 << (p / 2 % 64);
+            WHEEL_PRIMES[1..]
+                .iter()
+                .filter(|&&p| p < n)
+                .for_each(|&p| set(p));
+            while let Some(sieve) = segments.next_segment() {
+                sieve.primes().take_while(|&p| p < n).for_each(&mut set);
+            }
+        }
+        let mut ranks = Vec::with_capacity(bits.len().div_ceil(BLOCK) + 1);
+        ranks.push(0);
+        for block in bits.chunks(BLOCK) {
+            let count: u64 = block.iter().map(|word| u64::from(word.count_ones())).sum();
+            ranks.push(ranks[ranks.len() - 1] + count);
+        }
+        Self { bits, ranks, n }
+    }
+
+    /// Returns `true` if `k` is prime, or `false` otherwise.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is not less than the limit the bitmap was created with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let sieve = primes::sieve_below(1_000_000);
+    /// assert!(!sieve.is_prime(0));
+    /// assert!(!sieve.is_prime(1));
+    /// assert!(sieve.is_prime(2));
+    /// assert!(sieve.is_prime(999_983));
+    /// assert!(!sieve.is_prime(999_999));
+    ///
+    /// let below: Vec<_> = primes::below(1_000_000).collect();
+    /// let from_sieve: Vec<_> = (0..1_000_000).filter(|&k| sieve.is_prime(k)).collect();
+    /// assert_eq!(below, from_sieve);
+    /// ```
+    pub fn is_prime(&self, k: u64) -> bool {
+        assert!(k < self.n, "{k} is outside of a bitmap below {}", self.n);
+        if k.is_multiple_of(2) {
+            return k == 2;
+        }
+        let i = k / 2;
+        self.bits[as_index(i / 64)] & 1 << (i % 64) != 0
+    }
+
+    /// Returns the number of primes below the limit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert_eq!(primes::sieve_below(0).count(), 0);
+    /// assert_eq!(primes::sieve_below(3).count(), 1);
+    /// assert_eq!(primes::sieve_below(1_000_000).count(), 78_498);
+    /// ```
+    pub fn count(&self) -> u64 {
+        self.ranks[self.ranks.len() - 1] + u64::from(self.n > 2)
+    }
+
+    /// Returns the number of primes less than or equal to `k`, counting only those below the limit,
+    /// in constant time using the rank index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let n = 10_000_000;
+    /// let sieve = primes::sieve_below(n);
+    /// assert_eq!(sieve.count_below(0), 0);
+    /// assert_eq!(sieve.count_below(1), 0);
+    /// assert_eq!(sieve.count_below(2), 1);
+    /// assert_eq!(sieve.count_below(n - 1), 664_579);
+    /// assert_eq!(sieve.count_below(u64::MAX), 664_579);
+    ///
+    /// let mut count = 0;
+    /// let mut primes = primes::below(n).peekable();
+    /// for k in 0..n {
+    ///     if primes.next_if_eq(&k).is_some() {
+    ///         count += 1;
+    ///     }
+    ///     if k % 997 == 0 || k > n - 2_000 {
+    ///         assert_eq!(sieve.count_below(k), count);
+    ///     }
+    /// }
+    ///
+    /// for n in 0..300 {
+    ///     let sieve = primes::sieve_below(n);
+    ///     for k in 0..n + 3 {
+    ///         assert_eq!(sieve.count_below(k), primes::below(k.min(n.saturating_sub(1))).count() as u64);
+    ///     }
+    /// }
+    /// ```
+    pub fn count_below(&self, k: u64) -> u64 {
+        if k < 2 || self.n <= 2 {
+            return 0;
+        }
+        // The index of the largest odd number up to k, counting 1 as well although it is never set.
+        let i = (k.min(self.n - 1) - 1) / 2;
+        let word = as_index(i / 64);
+        let block = word / BLOCK;
+        let before: u64 = self.bits[block * BLOCK..word]
+            .iter()
+            .map(|word| u64::from(word.count_ones()))
+            .sum();
+        let within = self.bits[word] & u64::MAX >> (63 - i % 64);
+        1 + self.ranks[block] + before + u64::from(within.count_ones())
+    }
+
+    /// Returns the `k`th prime, numbered from 1 as [`nth`](crate::nth) does,
+    /// or `None` if there are fewer than `k` primes below the limit.
+    ///
+    /// The rank index is binary searched for the block holding it, and only that block is scanned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let n = 10_000_000;
+    /// let sieve = primes::sieve_below(n);
+    /// assert_eq!(sieve.nth(0), None);
+    /// assert_eq!(sieve.nth(1), Some(2));
+    /// assert_eq!(sieve.nth(2), Some(3));
+    /// assert_eq!(sieve.nth(664_579), Some(9_999_991));
+    /// assert_eq!(sieve.nth(664_580), None);
+    /// for (k, p) in primes::below(n).indexed().step_by(101) {
+    ///     assert_eq!(sieve.nth(k), Some(p));
+    /// }
+    ///
+    /// // Selecting the rank of the number before p gives the smallest prime at least p.
+    /// for p in (2..n).step_by(7_919).chain(n - 100..n) {
+    ///     let next = (p..).find(|&q| primes::is_prime(q)).filter(|&q| q < n);
+    ///     assert_eq!(sieve.nth(sieve.count_below(p - 1) + 1), next);
+    /// }
+    ///
+    /// assert_eq!(primes::sieve_below(2).nth(1), None);
+    /// assert_eq!(primes::sieve_below(3).nth(1), Some(2));
+    /// ```
+    pub fn nth(&self, k: u64) -> Option<u64> {
+        match k {
+            0 => return None,
+            1 => return (self.n > 2).then_some(2),
+            _ => {}
+        }
+        // The rank among the odd primes, from 0.
+        let mut rank = k - 2;
+        let block = self.ranks.partition_point(|&r| r <= rank);
+        if block == self.ranks.len() {
+            return None;
+        }
+        let block = block - 1;
+        rank -= self.ranks[block];
+        for (i, &word) in self.bits.iter().enumerate().skip(block * BLOCK) {
+            let count = u64::from(word.count_ones());
+            if rank < count {
+                let mut word = word;
+                for _ in 0..rank {
+                    word &= word - 1;
+                }
+                return Some((i as u64 * 64 + u64::from(word.trailing_zeros())) * 2 + 1);
+            }
+            rank -= count;
+        }
+        unreachable!("the rank index counts every bit")
+    }
+
+    /// Returns an iterator over the primes below the limit, in increasing order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let sieve = primes::sieve_below(1_000_000);
+    /// assert!(sieve.iter().eq(primes::below(1_000_000)));
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        let odd = self.bits.iter().enumerate().flat_map(|(i, &word)| {
+            let mut word = word;
+            core::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as u64;
+                word &= word - 1;
+                Some((i as u64 * 64 + bit) * 2 + 1)
+            })
+        });
+        (self.n > 2).then_some(2).into_iter().chain(odd)
+    }
+}
@@ -0,0 +1,116 @@
+//! Picks a default segment size from the size of the L1 data cache.
+
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+
+/// The environment variable that overrides the detected segment size, in bytes.
+#[cfg(feature = "std")]
+pub(crate) const ENV_VAR: &str = "PRIMES_SEGMENT_SIZE";
+
+/// The segment size used when the cache size can't be detected.
+pub(crate) const FALLBACK: usize = 32 * 1024;
+
+/// Returns the default segment size in bytes, detected once and then cached.
+#[cfg(feature = "std")]
+pub(crate) fn segment_size() -> usize {
+    static SIZE: OnceLock<usize> = OnceLock::new();
+    *SIZE.get_or_init(|| {
+        std::env::var(ENV_VAR)
+            .ok()
+            .and_then(|size| size.trim().parse().ok())
+            .filter(|&size| size > 0)
+            .or_else(l1_data_size)
+            .unwrap_or(FALLBACK)
+    })
+}
+
+/// Returns the default segment size in bytes.
+///
+/// Without `std` there is no environment variable to read or anywhere to cache the result,
+/// so this asks the CPU each time, which is cheap next to building a sieve.
+#[cfg(not(feature = "std"))]
+pub(crate) fn segment_size() -> usize {
+    l1_data_size().unwrap_or(FALLBACK)
+}
+
+/// Returns the size in bytes of the L1 data cache, if it can be detected.
+fn l1_data_size() -> Option<usize> {
+    sysfs_l1_data_size().or_else(cpuid_l1_data_size)
+}
+
+#[cfg(all(target_os = "linux", feature = "std"))]
+fn sysfs_l1_data_size() -> Option<usize> {
+    let read = |index: usize, file: &str| {
+        std::fs::read_to_string(std::format!(
+            "/sys/devices/system/cpu/cpu0/cache/index{index}/{file}"
+        ))
+        .ok()
+    };
+    (0..8)
+        .map_while(|index| Some((index, read(index, "level")?)))
+        .find_map(|(index, level)| {
+            let data = read(index, "type")?;
+            if level.trim() != "1" || !matches!(data.trim(), "Data" | "Unified") {
+                return None;
+            }
+            parse_size(&read(index, "size")?)
+        })
+}
+
+#[cfg(not(all(target_os = "linux", feature = "std")))]
+fn sysfs_l1_data_size() -> Option<usize> {
+    None
+}
+
+/// Parses a size like `48K` or `2M`, as reported by sysfs.
+#[cfg_attr(not(all(target_os = "linux", feature = "std")), allow(dead_code))]
+fn parse_size(size: &str) -> Option<usize> {
+    let size = size.trim();
+    let (digits, unit) = if let Some(digits) = size.strip_suffix('K') {
+        (digits, 1 << 10)
+    } else if let Some(digits) = size.strip_suffix('M') {
+        (digits, 1 << 20)
+    } else {
+        (size, 1)
+    };
+    let size = digits.parse::<usize>().ok()?.checked_mul(unit)?;
+    (size > 0).then_some(size)
+}
+
+#[cfg(target_arch = "x86_64")]
+fn cpuid_l1_data_size() -> Option<usize> {
+    use core::arch::x86_64::__cpuid;
+
+    let max_leaf = __cpuid(0).eax;
+    if max_leaf >= 4 {
+        // The deterministic cache parameters leaf, one subleaf per cache.
+        for subleaf in 0..8 {
+            let cache = core::arch::x86_64::__cpuid_count(4, subleaf);
+            let kind = cache.eax & 0x1f;
+            if kind == 0 {
+                break;
+            }
+            let level = (cache.eax >> 5) & 0x7;
+            if level == 1 && (kind == 1 || kind == 3) {
+                let ways = (cache.ebx >> 22) as usize + 1;
+                let partitions = ((cache.ebx >> 12) & 0x3ff) as usize + 1;
+                let line = (cache.ebx & 0xfff) as usize + 1;
+                let sets = cache.ecx as usize + 1;
+                return Some(ways * partitions * line * sets);
+            }
+        }
+    }
+    // AMD reports the L1 data cache size in KB in this extended leaf instead.
+    if __cpuid(0x8000_0000).eax >= 0x8000_0005 {
+        let kb = (__cpuid(0x8000_0005).ecx >> 24) as usize;
+        if kb > 0 {
+            return Some(kb << 10);
+        }
+    }
+    None
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn cpuid_l1_data_size() -> Option<usize> {
+    None
+}
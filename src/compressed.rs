@@ -0,0 +1,177 @@
+//! Primes stored compactly as the gaps between them.
+
+use alloc::vec::Vec;
+
+/// Gaps are stored as a single byte below this, and as this byte followed by the rest in a varint otherwise.
+const ESCAPE: u8 = u8::MAX;
+
+/// An increasing sequence of primes, stored as the gaps between them in about one byte each.
+///
+/// After 2, every gap between primes is even, so half of each gap, less one, is stored.
+/// That fits in a single byte for every gap up to 510, which covers every gap between primes below `3 * 10^11`,
+/// and larger gaps take a byte to mark them followed by a varint.
+/// So the 50,847,534 primes below `10^9` take 50,847,534 bytes, compared to 406,780,272 bytes as a `Vec<u64>`.
+///
+/// Any increasing sequence of odd numbers greater than 1, optionally starting with 2, can be stored,
+/// although only primes keep the gaps small.
+///
+/// # Examples
+///
+/// ```
+/// use primes::CompressedPrimes;
+///
+/// let primes: CompressedPrimes = primes::below(10_000_000).collect();
+/// assert_eq!(primes.len(), 664_579);
+/// assert_eq!(primes.last(), Some(9_999_991));
+/// assert!(primes.iter().eq(primes::below(10_000_000)));
+/// // One byte per prime, rather than eight.
+/// assert_eq!(primes.encoded_len(), 664_579);
+///
+/// // Starting from a prime other than 2.
+/// let primes: CompressedPrimes = primes::below(1_000_000).skip(10).collect();
+/// assert!(primes.iter().eq(primes::below(1_000_000).skip(10)));
+/// let mut primes = CompressedPrimes::new();
+/// primes.push(1_000_000_007);
+/// primes.push(1_000_000_009);
+/// assert_eq!(primes.iter().collect::<Vec<_>>(), [1_000_000_007, 1_000_000_009]);
+///
+/// // Gaps too large for a byte.
+/// let values = [2, 3, 5, 1_027, 1_029, 1 << 40 | 1, u64::MAX - 2, u64::MAX];
+/// let primes: CompressedPrimes = values.into_iter().collect();
+/// assert!(primes.iter().eq(values));
+/// assert_eq!(primes.last(), Some(u64::MAX));
+/// // The first gap between primes larger than 510 is the 514 after 304,599,508,537.
+/// let primes: CompressedPrimes = primes::between(304_599_508_000, 304_599_509_100).collect();
+/// assert!(primes.iter().eq(primes::between(304_599_508_000, 304_599_509_100)));
+/// assert!(primes.iter().any(|p| p == 304_599_508_537));
+/// assert_eq!(primes.encoded_len(), primes.len() + 7);
+///
+/// let empty = CompressedPrimes::new();
+/// assert!(empty.is_empty());
+/// assert_eq!(empty.last(), None);
+/// assert_eq!(empty.iter().next(), None);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CompressedPrimes {
+    bytes: Vec<u8>,
+    len: usize,
+    last: Option<u64>,
+}
+
+impl CompressedPrimes {
+    /// Returns an empty sequence.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `p` to the end of the sequence.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `p` is not greater than the last value, or is even and not 2, or is 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut primes = primes::CompressedPrimes::new();
+    /// primes.push(2);
+    /// primes.push(3);
+    /// primes.push(1_009);
+    /// assert_eq!(primes.len(), 3);
+    /// assert_eq!(primes.last(), Some(1_009));
+    /// ```
+    ///
+    /// ```should_panic
+    /// let mut primes = primes::CompressedPrimes::new();
+    /// primes.push(5);
+    /// primes.push(3);
+    /// ```
+    pub fn push(&mut self, p: u64) {
+        assert!(
+            p == 2 || p % 2 == 1 && p > 1,
+            "{p} is not 2 or an odd number above 1"
+        );
+        assert!(
+            self.last.is_none_or(|last| p > last),
+            "{p} is not greater than the last value"
+        );
+        // Each value is halved, rounding 2 down to the same as 1 would be, and is at least one more than the last.
+        let gap = (p - 1) / 2 - self.last.map_or(0, |last| (last - 1) / 2 + 1);
+        match u8::try_from(gap) {
+            Ok(byte) if byte < ESCAPE => self.bytes.push(byte),
+            _ => {
+                self.bytes.push(ESCAPE);
+                let mut rest = gap - ESCAPE as u64;
+                loop {
+                    let byte = (rest & 0x7f) as u8;
+                    rest >>= 7;
+                    if rest == 0 {
+                        self.bytes.push(byte);
+                        break;
+                    }
+                    self.bytes.push(byte | 0x80);
+                }
+            }
+        }
+        self.len += 1;
+        self.last = Some(p);
+    }
+
+    /// Returns the number of primes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there are no primes.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the last prime, or `None` if there are none.
+    pub fn last(&self) -> Option<u64> {
+        self.last
+    }
+
+    /// Returns the number of bytes the primes are encoded in.
+    pub fn encoded_len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns an iterator over the primes, in increasing order.
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        let mut bytes = self.bytes.iter();
+        let mut next = 0u64;
+        core::iter::from_fn(move || {
+            let mut gap = *bytes.next()? as u64;
+            if gap == ESCAPE as u64 {
+                let mut shift = 0;
+                for &byte in bytes.by_ref() {
+                    gap += ((byte & 0x7f) as u64) << shift;
+                    shift += 7;
+                    if byte < 0x80 {
+                        break;
+                    }
+                }
+            }
+            let half = next + gap;
+            next = half + 1;
+            Some(if half == 0 { 2 } else { half * 2 + 1 })
+        })
+    }
+}
+
+impl Extend<u64> for CompressedPrimes {
+    fn extend<I: IntoIterator<Item = u64>>(&mut self, iter: I) {
+        for p in iter {
+            self.push(p);
+        }
+    }
+}
+
+impl FromIterator<u64> for CompressedPrimes {
+    fn from_iter<I: IntoIterator<Item = u64>>(iter: I) -> Self {
+        let mut primes = Self::new();
+        primes.extend(iter);
+        primes
+    }
+}
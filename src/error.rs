@@ -0,0 +1,38 @@
+//! The error returned by the `try_` variants of the functions that have edge cases.
+
+use core::fmt;
+
+/// The error returned by [`try_nth`](crate::try_nth) and [`try_divisors`](crate::try_divisors),
+/// for inputs that the infallible functions handle with a silent edge case,
+/// and by [`below_as`](crate::below_as) and friends, for primes that wouldn't fit in the integer type asked for.
+///
+/// # Examples
+///
+/// ```
+/// use primes::Error;
+///
+/// assert_eq!(primes::try_nth(0), Err(Error::ZeroInput));
+/// assert_eq!(primes::try_nth(u64::MAX), Err(Error::OutOfRange));
+///
+/// let error: Box<dyn std::error::Error> = Box::new(Error::ZeroInput);
+/// assert_eq!(error.to_string(), "the input is 0, which has no meaningful answer");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Error {
+    /// The input is 0, which has no meaningful answer: there is no 0th prime, and 0 has no prime factorization.
+    ZeroInput,
+    /// The answer is too large to fit in a `u64`, or in the integer type asked for.
+    OutOfRange,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Error::ZeroInput => "the input is 0, which has no meaningful answer",
+            Error::OutOfRange => "the answer is too large for the integer type",
+        })
+    }
+}
+
+impl core::error::Error for Error {}
@@ -0,0 +1,58 @@
+//! The Euclid-Mullin sequence, from Euclid's proof that there are infinitely many primes.
+
+use core::iter::FusedIterator;
+
+use crate::smallest_factor_u128;
+
+/// Returns an iterator over the Euclid-Mullin sequence, in which each term is the smallest prime factor
+/// of one more than the product of the terms before it, starting from the empty product 1.
+///
+/// No term divides the product of the ones before it, so every term is a new prime, but far from in order.
+/// The product is kept in a `u128`, which holds it for the first 13 terms, so the iterator yields the first 14,
+/// up to `5,471`, and then stops rather than let the product wrap around.
+/// Each term is found by [`smallest_factor_u128`], so the few above `2^81` rest on a probable prime test.
+///
+/// # Examples
+///
+/// ```
+/// let terms: Vec<_> = primes::euclid_mullin().take(8).collect();
+/// assert_eq!(terms, [2, 3, 7, 43, 13, 53, 5, 6_221_671]);
+///
+/// let all: Vec<_> = primes::euclid_mullin().collect();
+/// assert_eq!(all.len(), 14);
+/// assert_eq!(all[8..], [38_709_183_810_571, 139, 2_801, 11, 17, 5_471]);
+///
+/// // Each term divides one more than the product before it, and is prime,
+/// // and multiplying in the last one is what overflows.
+/// let mut product = Some(1u128);
+/// for &term in &all {
+///     let before = product.unwrap();
+///     assert_eq!((before + 1) % term, 0);
+///     assert!(primes::is_prime_u128(term));
+///     product = before.checked_mul(term);
+/// }
+/// assert_eq!(product, None);
+/// ```
+pub fn euclid_mullin() -> EuclidMullin {
+    EuclidMullin { product: Some(1) }
+}
+
+/// An iterator over the Euclid-Mullin sequence, created by [`euclid_mullin`].
+#[derive(Clone, Debug)]
+pub struct EuclidMullin {
+    /// The product of the terms so far, or `None` once it no longer fits.
+    product: Option<u128>,
+}
+
+impl Iterator for EuclidMullin {
+    type Item = u128;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let product = self.product.take()?;
+        let term = smallest_factor_u128(product.checked_add(1)?)?;
+        self.product = product.checked_mul(term);
+        Some(term)
+    }
+}
+
+impl FusedIterator for EuclidMullin {}
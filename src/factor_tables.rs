@@ -0,0 +1,184 @@
+//! Tables of prime factors for every number below a limit.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::sieve::as_index;
+use crate::{isqrt, strictly_below, up_to};
+
+/// Returns a table of the smallest prime factor of every number less than `n`.
+///
+/// Any number in the table can then be factored by repeated division, see [`SmallestPrimeFactors::factorize`].
+///
+/// Takes 4 bytes per number.
+///
+/// # Panics
+///
+/// Panics if `n` is greater than `2^32`, as the factors are stored as `u32`.
+///
+/// # Examples
+///
+/// ```
+/// let spf = primes::spf_below(16);
+/// assert_eq!(spf.as_slice(), &[0, 0, 2, 3, 2, 5, 2, 7, 2, 3, 2, 11, 2, 13, 2, 3]);
+///
+/// let factors: Vec<_> = spf.factorize(12).collect();
+/// assert_eq!(&factors, &[(2, 2), (3, 1)]);
+/// ```
+pub fn spf_below(n: usize) -> SmallestPrimeFactors {
+    SmallestPrimeFactors::new(n)
+}
+
+/// The smallest prime factor of every number below a limit.
+///
+/// Created by [`spf_below`].
+pub struct SmallestPrimeFactors {
+    spf: Vec<u32>,
+}
+
+impl SmallestPrimeFactors {
+    fn new(n: usize) -> Self {
+        assert!(
+            n as u64 <= 1 << 32,
+            "smallest prime factors below {n} do not fit in u32"
+        );
+        let mut spf = vec![0; n];
+        if n > 2 {
+            for p in up_to(isqrt(n as u64 - 1)) {
+                let (factor, p) = (u32::try_from(p).unwrap(), as_index(p));
+                for m in (p * p..n).step_by(p) {
+                    if spf[m] == 0 {
+                        spf[m] = factor;
+                    }
+                }
+            }
+            for (k, factor) in spf.iter_mut().enumerate().skip(2) {
+                if *factor == 0 {
+                    *factor = u32::try_from(k).unwrap();
+                }
+            }
+        }
+        Self { spf }
+    }
+
+    /// Returns the smallest prime factor of `k`, or `0` if `k` is `0` or `1`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is not less than the limit the table was created with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let spf = primes::spf_below(100_000);
+    /// assert_eq!(spf.get(0), 0);
+    /// assert_eq!(spf.get(1), 0);
+    /// assert_eq!(spf.get(91), 7);
+    ///
+    /// for p in primes::below(99_999) {
+    ///     assert_eq!(spf.get(p as usize), p as u32);
+    /// }
+    /// ```
+    pub fn get(&self, k: usize) -> u32 {
+        self.spf[k]
+    }
+
+    /// Returns the whole table, indexed by number.
+    pub fn as_slice(&self) -> &[u32] {
+        &self.spf
+    }
+
+    /// Returns an iterator over the prime divisors of `k`, and their exponents, in the same form as [`divisors`](crate::divisors).
+    ///
+    /// Takes `O(log k)` divisions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is not less than the limit the table was created with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let spf = primes::spf_below(100_000);
+    /// for k in 1..100_000 {
+    ///     let product: u64 = spf.factorize(k).map(|(p, e)| p.pow(e as u32)).product();
+    ///     assert_eq!(product, k as u64);
+    /// }
+    /// for k in (0..100_000).step_by(7) {
+    ///     assert!(spf.factorize(k).eq(primes::divisors(k as u64)));
+    /// }
+    /// ```
+    pub fn factorize(&self, k: usize) -> impl Iterator<Item = (u64, u64)> + '_ {
+        assert!(
+            k < self.spf.len(),
+            "{k} is outside of a table below {}",
+            self.spf.len()
+        );
+        // 0 has no prime factorization, matching `divisors`.
+        let mut k = k.max(1);
+        core::iter::from_fn(move || {
+            if k == 1 {
+                return None;
+            }
+            let prime = self.spf[k] as usize;
+            let mut exponent = 0;
+            while k.is_multiple_of(prime) {
+                k /= prime;
+                exponent += 1;
+            }
+            Some((prime as u64, exponent))
+        })
+    }
+}
+
+/// Returns a table of the largest prime factor of every number less than `n`, with `0` for `0` and `1`.
+///
+/// Takes 4 bytes per number.
+///
+/// # Panics
+///
+/// Panics if `n` is greater than `2^32`, as the factors are stored as `u32`.
+///
+/// # Examples
+///
+/// ```
+/// let lpf = primes::lpf_below(16);
+/// assert_eq!(&lpf, &[0, 0, 2, 3, 2, 5, 3, 7, 2, 3, 5, 11, 3, 13, 7, 5]);
+///
+/// let lpf = primes::lpf_below(100_000);
+/// for p in primes::below(99_999) {
+///     assert_eq!(lpf[p as usize], p as u32);
+/// }
+/// for k in 1..17 {
+///     assert_eq!(lpf[1 << k], 2);
+/// }
+///
+/// let smooth = lpf.iter().skip(1).filter(|&&p| p <= 10).count();
+/// let brute_force = (1..100_000u64)
+///     .filter(|&k| {
+///         let mut k = k;
+///         for p in [2, 3, 5, 7] {
+///             while k % p == 0 {
+///                 k /= p;
+///             }
+///         }
+///         k == 1
+///     })
+///     .count();
+/// assert_eq!(smooth, brute_force);
+/// ```
+pub fn lpf_below(n: usize) -> Vec<u32> {
+    assert!(
+        n as u64 <= 1 << 32,
+        "largest prime factors below {n} do not fit in u32"
+    );
+    let mut lpf = vec![0; n];
+    // Primes are visited in increasing order, so the last to write to an entry is the largest.
+    for p in strictly_below(n as u64) {
+        let (factor, p) = (u32::try_from(p).unwrap(), as_index(p));
+        for m in (p..n).step_by(p) {
+            lpf[m] = factor;
+        }
+    }
+    lpf
+}
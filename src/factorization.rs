@@ -0,0 +1,310 @@
+//! Prime factorizations as values, checked to be well formed, for storing and exchanging them.
+
+use alloc::vec::Vec;
+
+use core::fmt;
+
+use crate::{is_prime, try_divisors, Error};
+
+/// The prime factorization of a positive number, as the `(prime, exponent)` pairs [`divisors`](crate::divisors) yields:
+/// primes in increasing order, each with an exponent of at least 1, whose product fits in a `u64`.
+/// The factorization of 1 has no pairs.
+///
+/// Every `Factorization` has increasing entries of at least 2, nonzero exponents and a product that fits,
+/// however it was made. Only [`of`](Factorization::of), [`new_checked`](Factorization::new_checked)
+/// and, with the `serde` feature, `deserialize_checked` also guarantee that every entry is prime:
+/// [`new`](Factorization::new) and the default `Deserialize` impl skip the primality tests,
+/// so accept composite entries such as `[(4, 1)]`.
+///
+/// With the `serde` feature, it is serialized as a sequence of `[prime, exponent]` pairs,
+/// such as `[[2,3],[3,2],[5,1]]` for 360 in JSON, as in [`FactorizationFormat::JsonLines`](crate::FactorizationFormat::JsonLines).
+/// That form is stable, and deserializing checks it as [`new`](Factorization::new) does,
+/// or as [`new_checked`](Factorization::new_checked) does with [`deserialize_checked`](Factorization::deserialize_checked).
+///
+/// # Examples
+///
+/// ```
+/// use primes::Factorization;
+///
+/// let factorization = Factorization::of(360).unwrap();
+/// assert_eq!(factorization.pairs(), [(2, 3), (3, 2), (5, 1)]);
+/// assert_eq!(factorization.value(), 360);
+/// assert_eq!(factorization.to_string(), "2^3 * 3^2 * 5");
+///
+/// assert_eq!(Factorization::new(vec![(2, 3), (3, 2), (5, 1)]), Ok(factorization));
+/// assert_eq!(Factorization::of(1).unwrap().to_string(), "1");
+/// assert!(Factorization::of(0).is_err());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Factorization {
+    pairs: Vec<(u64, u64)>,
+    value: u64,
+}
+
+/// Why pairs given to [`Factorization::new`] or [`Factorization::new_checked`] aren't a factorization.
+///
+/// Each variant holds the index of the first offending pair.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FactorizationError {
+    /// The prime isn't larger than the one before it.
+    NotIncreasing(usize),
+    /// The exponent is 0.
+    ZeroExponent(usize),
+    /// The prime is 0 or 1, or, when checked, composite.
+    NotPrime(usize),
+    /// The product of the pairs up to this one doesn't fit in a `u64`.
+    Overflow(usize),
+}
+
+impl fmt::Display for FactorizationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FactorizationError::NotIncreasing(i) => {
+                write!(
+                    f,
+                    "the prime in pair {i} isn't larger than the one before it"
+                )
+            }
+            FactorizationError::ZeroExponent(i) => write!(f, "the exponent in pair {i} is 0"),
+            FactorizationError::NotPrime(i) => write!(f, "the prime in pair {i} isn't prime"),
+            FactorizationError::Overflow(i) => {
+                write!(f, "the product up to pair {i} is too large for a u64")
+            }
+        }
+    }
+}
+
+impl core::error::Error for FactorizationError {}
+
+impl Factorization {
+    /// Returns the prime factorization of `n`, found with [`divisors`](crate::divisors).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ZeroInput`] if `n` is 0, which has no factorization.
+    pub fn of(n: u64) -> Result<Factorization, Error> {
+        Ok(Factorization {
+            pairs: try_divisors(n)?.collect(),
+            value: n,
+        })
+    }
+
+    /// Checks that `pairs` are a factorization, without checking that the primes are prime, beyond being at least 2.
+    ///
+    /// That takes a pass over the pairs, and is what deserializing does by default.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first thing wrong with the pairs, in order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use primes::{Factorization, FactorizationError};
+    ///
+    /// assert_eq!(Factorization::new(vec![]).unwrap().value(), 1);
+    /// assert_eq!(Factorization::new(vec![(3, 1), (2, 1)]), Err(FactorizationError::NotIncreasing(1)));
+    /// assert_eq!(Factorization::new(vec![(3, 1), (3, 1)]), Err(FactorizationError::NotIncreasing(1)));
+    /// assert_eq!(Factorization::new(vec![(2, 1), (3, 0)]), Err(FactorizationError::ZeroExponent(1)));
+    /// assert_eq!(Factorization::new(vec![(1, 5)]), Err(FactorizationError::NotPrime(0)));
+    /// assert_eq!(Factorization::new(vec![(2, 64)]), Err(FactorizationError::Overflow(0)));
+    /// assert_eq!(Factorization::new(vec![(2, 63), (3, 1)]), Err(FactorizationError::Overflow(1)));
+    /// assert_eq!(Factorization::new(vec![(2, 63)]).unwrap().value(), 1 << 63);
+    ///
+    /// // Composite "primes" are only caught by `new_checked`.
+    /// assert_eq!(Factorization::new(vec![(4, 1)]).unwrap().value(), 4);
+    /// ```
+    pub fn new(pairs: Vec<(u64, u64)>) -> Result<Factorization, FactorizationError> {
+        let mut value = 1u64;
+        let mut last = 1;
+        for (i, &(p, e)) in pairs.iter().enumerate() {
+            if p < 2 {
+                return Err(FactorizationError::NotPrime(i));
+            }
+            if p <= last {
+                return Err(FactorizationError::NotIncreasing(i));
+            }
+            if e == 0 {
+                return Err(FactorizationError::ZeroExponent(i));
+            }
+            // Any exponent past 63 overflows, as every prime is at least 2.
+            value = u32::try_from(e)
+                .ok()
+                .and_then(|e| p.checked_pow(e))
+                .and_then(|power| value.checked_mul(power))
+                .ok_or(FactorizationError::Overflow(i))?;
+            last = p;
+        }
+        Ok(Factorization { pairs, value })
+    }
+
+    /// Checks that `pairs` are a factorization as [`new`](Factorization::new) does,
+    /// and also that every prime is prime, which takes a primality test for each.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first thing wrong with the pairs, in order, with composite primes found after everything else.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use primes::{Factorization, FactorizationError};
+    ///
+    /// assert_eq!(Factorization::new_checked(vec![(3, 1), (561, 1)]), Err(FactorizationError::NotPrime(1)));
+    /// assert_eq!(Factorization::new_checked(vec![(2, 1), (9, 1)]), Err(FactorizationError::NotPrime(1)));
+    /// assert_eq!(Factorization::new_checked(vec![(4_294_967_291, 2)]).unwrap().value(), 4_294_967_291u64.pow(2));
+    /// ```
+    pub fn new_checked(pairs: Vec<(u64, u64)>) -> Result<Factorization, FactorizationError> {
+        let factorization = Factorization::new(pairs)?;
+        match factorization.pairs.iter().position(|&(p, _)| !is_prime(p)) {
+            Some(i) => Err(FactorizationError::NotPrime(i)),
+            None => Ok(factorization),
+        }
+    }
+
+    /// Returns the `(prime, exponent)` pairs, with the primes in increasing order.
+    pub fn pairs(&self) -> &[(u64, u64)] {
+        &self.pairs
+    }
+
+    /// Returns the number factorized, the product of the prime powers.
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+
+    /// Returns the `(prime, exponent)` pairs, with the primes in increasing order.
+    pub fn into_pairs(self) -> Vec<(u64, u64)> {
+        self.pairs
+    }
+}
+
+/// Writes the factorization as [`FactorizationFormat::Text`](crate::FactorizationFormat::Text) does,
+/// such as `2^3 * 3^2 * 5`, or `1` for the factorization of 1.
+impl fmt::Display for Factorization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.pairs.is_empty() {
+            return f.write_str("1");
+        }
+        let mut separator = "";
+        for &(p, e) in &self.pairs {
+            f.write_str(separator)?;
+            if e == 1 {
+                write!(f, "{p}")?;
+            } else {
+                write!(f, "{p}^{e}")?;
+            }
+            separator = " * ";
+        }
+        Ok(())
+    }
+}
+
+/// Writes the factorization as a sequence of `[prime, exponent]` pairs.
+///
+/// Only available with the `serde` feature.
+///
+/// # Examples
+///
+/// ```
+/// use primes::Factorization;
+///
+/// let json = serde_json::to_string(&Factorization::of(360).unwrap())?;
+/// assert_eq!(json, "[[2,3],[3,2],[5,1]]");
+/// assert_eq!(serde_json::to_string(&Factorization::of(1).unwrap())?, "[]");
+///
+/// for n in (1..10_000).step_by(7).chain([u64::MAX, (1 << 61) - 1]) {
+///     let factorization = Factorization::of(n).unwrap();
+///     let json = serde_json::to_string(&factorization)?;
+///     assert_eq!(serde_json::from_str::<Factorization>(&json)?, factorization);
+/// }
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+#[cfg(feature = "serde")]
+impl serde::Serialize for Factorization {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.pairs.serialize(serializer)
+    }
+}
+
+/// Reads a sequence of `[prime, exponent]` pairs, checking them as [`Factorization::new`] does.
+///
+/// Only available with the `serde` feature.
+///
+/// # Examples
+///
+/// ```
+/// use primes::Factorization;
+///
+/// let factorization: Factorization = serde_json::from_str("[[2, 3], [3, 2], [5, 1]]")?;
+/// assert_eq!(factorization.value(), 360);
+///
+/// for (json, message) in [
+///     ("[[3,1],[2,1]]", "the prime in pair 1 isn't larger than the one before it"),
+///     ("[[2,1],[3,0]]", "the exponent in pair 1 is 0"),
+///     ("[[0,1]]", "the prime in pair 0 isn't prime"),
+///     ("[[2,40],[3,20]]", "the product up to pair 1 is too large for a u64"),
+/// ] {
+///     let error = serde_json::from_str::<Factorization>(json).unwrap_err();
+///     assert!(error.to_string().starts_with(&format!("invalid factorization: {message}")));
+/// }
+/// assert!(serde_json::from_str::<Factorization>("[[2,-1]]").is_err());
+/// assert!(serde_json::from_str::<Factorization>("[[2,1,1]]").is_err());
+///
+/// // Composite primes only fail with `deserialize_checked`.
+/// assert_eq!(serde_json::from_str::<Factorization>("[[4,1]]")?.value(), 4);
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Factorization {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let pairs = Vec::deserialize(deserializer)?;
+        Factorization::new(pairs)
+            .map_err(|error| D::Error::custom(format_args!("invalid factorization: {error}")))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Factorization {
+    /// Reads a factorization as its [`Deserialize`](serde::Deserialize) impl does,
+    /// but checking it as [`new_checked`](Factorization::new_checked) does, so that composite primes are rejected too,
+    /// for `#[serde(deserialize_with = "Factorization::deserialize_checked")]`.
+    ///
+    /// Only available with the `serde` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error from the deserializer if it doesn't hold a sequence of pairs, or they aren't a factorization.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use primes::Factorization;
+    ///
+    /// #[derive(serde::Deserialize)]
+    /// struct Row {
+    ///     n: u64,
+    ///     #[serde(deserialize_with = "Factorization::deserialize_checked")]
+    ///     factors: Factorization,
+    /// }
+    ///
+    /// let row: Row = serde_json::from_str(r#"{"n":360,"factors":[[2,3],[3,2],[5,1]]}"#)?;
+    /// assert_eq!(row.factors.value(), row.n);
+    ///
+    /// let error = serde_json::from_str::<Row>(r#"{"n":12,"factors":[[2,1],[6,1]]}"#).err().unwrap();
+    /// assert!(error.to_string().starts_with("invalid factorization: the prime in pair 1 isn't prime"));
+    /// # Ok::<(), serde_json::Error>(())
+    /// ```
+    pub fn deserialize_checked<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Factorization, D::Error> {
+        use serde::de::Error;
+        use serde::Deserialize;
+
+        let pairs = Vec::deserialize(deserializer)?;
+        Factorization::new_checked(pairs)
+            .map_err(|error| D::Error::custom(format_args!("invalid factorization: {error}")))
+    }
+}
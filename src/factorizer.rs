@@ -0,0 +1,154 @@
+//! A factorization context that keeps its own table of primes between calls.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use core::fmt;
+
+use crate::{between, count_upper_bound, is_prime, isqrt, Divisors};
+
+/// The largest prime the table can need, as every `u64` has a prime factor no larger than this, or is prime.
+const MAX_TABLE: u64 = u32::MAX as u64;
+
+/// Factorizes numbers by trial division by a table of primes that it owns,
+/// grown as the numbers it is asked about get larger, and reused from one call to the next.
+///
+/// [`divisors`](crate::divisors) shares a process-wide table with every thread, up to `2^24`,
+/// and sieves the primes it needs afresh for anything larger.
+/// A `Factorizer` is the explicit alternative: one can be kept per thread, or per job,
+/// and its table goes up to `sqrt(n)` for the largest `n` it has factorized, however large that is,
+/// so that the memory it takes is under the caller's control, and freed when it is dropped.
+/// The table holds each prime as a `u32`, so the primes up to `sqrt(2^64)` take about 810 MB,
+/// and the `Factorizer` only stays small if the composite numbers it factorizes do too.
+///
+/// Without `std`, where [`divisors`](crate::divisors) has no shared table, this is the way to reuse one.
+///
+/// # Examples
+///
+/// ```
+/// use primes::Factorizer;
+///
+/// let mut factorizer = Factorizer::new();
+/// assert_eq!(factorizer.factor(504).collect::<Vec<_>>(), [(2, 3), (3, 2), (7, 1)]);
+/// assert_eq!(factorizer.smallest_factor(1_000_003 * 999_983), Some(999_983));
+/// assert!(factorizer.is_prime(1_000_003));
+///
+/// // The table reaches the square root of the largest number factorized, and is kept for the next call.
+/// assert!(factorizer.table_bound() >= 1_000);
+/// let bound = factorizer.table_bound();
+/// factorizer.factor(12).for_each(drop);
+/// assert_eq!(factorizer.table_bound(), bound);
+/// ```
+///
+/// It gives the same answers as [`divisors`](crate::divisors) and [`is_prime`](crate::is_prime),
+/// growing its table only when a number needs more of it than it has:
+///
+/// ```
+/// use primes::Factorizer;
+///
+/// let mut factorizer = Factorizer::new();
+/// assert_eq!(factorizer.table_bound(), 0);
+/// let mut seed = 1u64;
+/// for _ in 0..2_000 {
+///     seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+///     let n = seed >> (seed % 32 + 16);
+///     let before = factorizer.table_bound();
+///     assert!(factorizer.factor(n).eq(primes::divisors(n)));
+///     assert_eq!(factorizer.is_prime(n), primes::is_prime(n));
+///     assert_eq!(factorizer.smallest_factor(n), primes::divisors(n).next().map(|(p, _)| p));
+///
+///     let after = factorizer.table_bound();
+///     if primes::isqrt(n) <= before || primes::is_prime(n) {
+///         assert_eq!(after, before);
+///     } else {
+///         assert!(after >= primes::isqrt(n));
+///         assert_eq!(factorizer.table_len(), primes::below(after).count());
+///     }
+/// }
+///
+/// let p = 16_777_259;
+/// assert_eq!(factorizer.factor(p * p).collect::<Vec<_>>(), [(p, 2)]);
+/// assert_eq!(factorizer.smallest_factor(p * 1_000_003), Some(1_000_003));
+/// ```
+#[derive(Clone, Default)]
+pub struct Factorizer {
+    /// Every prime up to `bound`, in order, shared with the [`Divisors`] iterators handed out.
+    primes: Arc<[u32]>,
+    bound: u64,
+}
+
+impl Factorizer {
+    /// Returns a `Factorizer` with an empty table, which takes no memory until it is first used.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns an iterator over the prime divisors of `n`, and their exponents, as [`divisors`](crate::divisors) does,
+    /// first growing the table to reach `sqrt(n)` if it doesn't already.
+    ///
+    /// A prime is its own factorization, so it is recognised without growing the table.
+    /// The iterator shares the table rather than borrowing the `Factorizer`, so it can be kept while others are made.
+    pub fn factor(&mut self, n: u64) -> Divisors {
+        let root = isqrt(n);
+        // Testing first is only worth it when a prime would otherwise grow the table.
+        // With no primes left to try, whatever trial division leaves is yielded as prime, which a prime `n` is.
+        if root > self.bound && !is_prime(n) {
+            self.reserve(root);
+        }
+        Divisors::with_table(n, Arc::clone(&self.primes))
+    }
+
+    /// Returns `true` if `n` is prime, or `false` otherwise, as [`is_prime`](crate::is_prime) does.
+    ///
+    /// Primality is settled by a deterministic Miller-Rabin test, which needs no table, so this never grows it.
+    pub fn is_prime(&mut self, n: u64) -> bool {
+        is_prime(n)
+    }
+
+    /// Returns the smallest prime factor of `n`, or `None` for 0 and 1, which have none.
+    ///
+    /// A prime is its own smallest factor, and is recognised without growing the table.
+    pub fn smallest_factor(&mut self, n: u64) -> Option<u64> {
+        self.factor(n).next().map(|(p, _)| p)
+    }
+
+    /// Returns the bound the table covers: it holds every prime less than or equal to this.
+    pub fn table_bound(&self) -> u64 {
+        self.bound
+    }
+
+    /// Returns the number of primes in the table.
+    pub fn table_len(&self) -> usize {
+        self.primes.len()
+    }
+
+    /// Grows the table to cover every prime up to `n`, if it doesn't already.
+    ///
+    /// Like the shared table behind [`divisors`](crate::divisors), it grows at least geometrically,
+    /// so that a run of increasing numbers only sieves a handful of times.
+    /// Only the primes past the old bound are sieved, but the table is copied,
+    /// as iterators from [`factor`](Factorizer::factor) may still hold the old one.
+    pub fn reserve(&mut self, n: u64) {
+        if n <= self.bound {
+            return;
+        }
+        let bound = n
+            .max(self.bound.saturating_mul(2))
+            .clamp(1 << 10, MAX_TABLE);
+        let mut primes = Vec::with_capacity(usize::try_from(count_upper_bound(bound)).unwrap_or(0));
+        primes.extend_from_slice(&self.primes);
+        primes.extend(between(self.bound + 1, bound).map(|p| u32::try_from(p).unwrap()));
+        self.primes = primes.into();
+        self.bound = bound;
+    }
+}
+
+impl fmt::Debug for Factorizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The table can be large, so only how far it reaches is shown.
+        f.debug_struct("Factorizer")
+            .field("table_bound", &self.bound)
+            .field("table_len", &self.primes.len())
+            .finish()
+    }
+}
@@ -0,0 +1,210 @@
+//! A C ABI for embedding the crate in other languages, declared in `include/primes.h`.
+//!
+//! No panic crosses the boundary: each function catches any panic and reports it as an error instead.
+//!
+//! The library is built as a C dynamic library with
+//!
+//! ```sh
+//! cargo rustc --lib --release --features ffi --crate-type cdylib
+//! ```
+//!
+//! rather than listing `cdylib` in `Cargo.toml`, as that would need a panic handler and allocator
+//! in builds without `std`, which are left to the final binary.
+
+use std::boxed::Box;
+use std::os::raw::c_int;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+use crate::{below, is_prime, nth, Primes};
+
+/// Returned on success.
+pub const PRIMES_OK: c_int = 0;
+/// Returned when there is no answer: there is no `0`th prime, or an iterator is exhausted.
+pub const PRIMES_NONE: c_int = 1;
+/// Returned when a pointer that must not be null is null.
+pub const PRIMES_NULL: c_int = -1;
+/// Returned when the call panicked, which is a bug in the crate.
+pub const PRIMES_PANIC: c_int = -2;
+
+/// An iterator over primes, handed out to C as an opaque pointer by [`primes_iter_new_below`].
+pub struct PrimesIter(Primes);
+
+/// Returns `true` if `n` is prime, as [`is_prime`] does.
+///
+/// # Examples
+///
+/// ```
+/// use primes::ffi::primes_is_prime;
+///
+/// assert!(primes_is_prime(18_446_744_073_709_551_557));
+/// assert!(!primes_is_prime(1));
+/// ```
+#[no_mangle]
+pub extern "C" fn primes_is_prime(n: u64) -> bool {
+    catch_unwind(|| is_prime(n)).unwrap_or(false)
+}
+
+/// Writes the `n`th prime, numbered from 1 as [`nth`] does, to `out`.
+///
+/// Returns [`PRIMES_OK`], [`PRIMES_NONE`] if there is no `n`th prime in a `u64`, or [`PRIMES_NULL`] if `out` is null.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writing a `u64`.
+///
+/// # Examples
+///
+/// ```
+/// use primes::ffi::{primes_nth, PRIMES_NONE, PRIMES_NULL, PRIMES_OK};
+///
+/// let mut p = 0;
+/// assert_eq!(unsafe { primes_nth(100, &mut p) }, PRIMES_OK);
+/// assert_eq!(p, 541);
+/// assert_eq!(unsafe { primes_nth(0, &mut p) }, PRIMES_NONE);
+/// assert_eq!(p, 541);
+/// assert_eq!(unsafe { primes_nth(100, std::ptr::null_mut()) }, PRIMES_NULL);
+/// ```
+#[no_mangle]
+pub unsafe extern "C" fn primes_nth(n: u64, out: *mut u64) -> c_int {
+    if out.is_null() {
+        return PRIMES_NULL;
+    }
+    match catch_unwind(|| nth(n)) {
+        Ok(Some(p)) => {
+            // SAFETY: `out` is non-null, and the caller guarantees it is valid for writes.
+            unsafe { out.write(p) };
+            PRIMES_OK
+        }
+        Ok(None) => PRIMES_NONE,
+        Err(_) => PRIMES_PANIC,
+    }
+}
+
+/// Writes the primes less than or equal to `limit` to `buf`, up to `cap` of them,
+/// and returns how many there are in total, so that a caller can size the buffer with a first call.
+///
+/// `buf` may be null if `cap` is 0. Returns `SIZE_MAX` if the call panicked.
+///
+/// # Safety
+///
+/// `buf` must be valid for writing `cap` `u64`s, unless `cap` is 0.
+///
+/// # Examples
+///
+/// ```
+/// use primes::ffi::primes_below;
+///
+/// let needed = unsafe { primes_below(100, std::ptr::null_mut(), 0) };
+/// assert_eq!(needed, 25);
+/// let mut buf = vec![0; needed];
+/// assert_eq!(unsafe { primes_below(100, buf.as_mut_ptr(), buf.len()) }, 25);
+/// assert_eq!(buf, primes::below_vec(100));
+///
+/// let mut short = [0; 4];
+/// assert_eq!(unsafe { primes_below(100, short.as_mut_ptr(), 4) }, 25);
+/// assert_eq!(short, [2, 3, 5, 7]);
+/// ```
+#[no_mangle]
+pub unsafe extern "C" fn primes_below(limit: u64, buf: *mut u64, cap: usize) -> usize {
+    catch_unwind(AssertUnwindSafe(|| {
+        let mut count = 0;
+        for p in below(limit) {
+            if count < cap {
+                // SAFETY: the caller guarantees `buf` is valid for `cap` writes.
+                unsafe { buf.add(count).write(p) };
+            }
+            count += 1;
+        }
+        count
+    }))
+    .unwrap_or(usize::MAX)
+}
+
+/// Returns a new iterator over the primes less than or equal to `limit`,
+/// to be advanced with [`primes_iter_next`] and freed with [`primes_iter_free`].
+///
+/// Returns null if the call panicked.
+///
+/// # Examples
+///
+/// ```
+/// use primes::ffi::{primes_iter_free, primes_iter_new_below, primes_iter_next, PRIMES_NONE, PRIMES_NULL, PRIMES_OK};
+///
+/// let mut iter = primes_iter_new_below(30);
+/// assert!(!iter.is_null());
+/// let mut collected = Vec::new();
+/// let mut p = 0;
+/// while unsafe { primes_iter_next(iter, &mut p) } == PRIMES_OK {
+///     collected.push(p);
+/// }
+/// assert_eq!(collected, [2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+/// assert_eq!(unsafe { primes_iter_next(iter, &mut p) }, PRIMES_NONE);
+/// assert_eq!(unsafe { primes_iter_next(iter, std::ptr::null_mut()) }, PRIMES_NULL);
+///
+/// // Freeing nulls out the handle, so freeing it again, or using it afterwards, is caught.
+/// unsafe { primes_iter_free(&mut iter) };
+/// assert!(iter.is_null());
+/// unsafe { primes_iter_free(&mut iter) };
+/// assert_eq!(unsafe { primes_iter_next(iter, &mut p) }, PRIMES_NULL);
+/// unsafe { primes_iter_free(std::ptr::null_mut()) };
+///
+/// // An iterator can be dropped before it is exhausted.
+/// let mut iter = primes_iter_new_below(u64::MAX);
+/// assert_eq!(unsafe { primes_iter_next(iter, &mut p) }, PRIMES_OK);
+/// assert_eq!(p, 2);
+/// unsafe { primes_iter_free(&mut iter) };
+/// ```
+#[no_mangle]
+pub extern "C" fn primes_iter_new_below(limit: u64) -> *mut PrimesIter {
+    catch_unwind(|| Box::into_raw(Box::new(PrimesIter(below(limit))))).unwrap_or(ptr::null_mut())
+}
+
+/// Writes the next prime from `iter` to `out`.
+///
+/// Returns [`PRIMES_OK`], [`PRIMES_NONE`] once the iterator is exhausted, or [`PRIMES_NULL`] if either pointer is null.
+///
+/// # Safety
+///
+/// `iter` must be null or a live iterator from [`primes_iter_new_below`], not used by another thread at the same time,
+/// and `out` must be null or valid for writing a `u64`.
+#[no_mangle]
+pub unsafe extern "C" fn primes_iter_next(iter: *mut PrimesIter, out: *mut u64) -> c_int {
+    if iter.is_null() || out.is_null() {
+        return PRIMES_NULL;
+    }
+    // SAFETY: `iter` is non-null, and the caller guarantees it is live and not aliased.
+    let iter = unsafe { &mut *iter };
+    match catch_unwind(AssertUnwindSafe(|| iter.0.next())) {
+        Ok(Some(p)) => {
+            // SAFETY: `out` is non-null, and the caller guarantees it is valid for writes.
+            unsafe { out.write(p) };
+            PRIMES_OK
+        }
+        Ok(None) => PRIMES_NONE,
+        Err(_) => PRIMES_PANIC,
+    }
+}
+
+/// Frees the iterator `*iter` and sets `*iter` to null, so that freeing it again does nothing.
+///
+/// Does nothing if `iter` or `*iter` is null.
+///
+/// # Safety
+///
+/// `iter` must be null or valid for reading and writing a pointer,
+/// and `*iter` must be null or a live iterator from [`primes_iter_new_below`].
+/// Copies of the handle made before it was freed are not nulled out, and must not be used.
+#[no_mangle]
+pub unsafe extern "C" fn primes_iter_free(iter: *mut *mut PrimesIter) {
+    if iter.is_null() {
+        return;
+    }
+    // SAFETY: the caller guarantees `iter` is valid for reads and writes.
+    let handle = unsafe { iter.replace(ptr::null_mut()) };
+    if !handle.is_null() {
+        // SAFETY: the caller guarantees `handle` came from `Box::into_raw` in `primes_iter_new_below`,
+        // and it has just been nulled out, so it can't be freed twice through `iter`.
+        drop(unsafe { Box::from_raw(handle) });
+    }
+}
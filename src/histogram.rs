@@ -0,0 +1,71 @@
+//! Counts of primes over fixed-width intervals.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::sieve::{as_index, Segments, WHEEL_PRIMES};
+
+/// Returns the number of primes in each interval `[0, w)`, `[w, 2w)`, ... of width `w = bucket_width`
+/// that starts below `limit`, counting only the primes less than `limit`, so the last interval may be cut short.
+///
+/// Like [`sieve_below`](crate::sieve_below), `limit` itself is left out, so the counts add up to the number of primes below it.
+/// The primes are never yielded: each sieve segment is split at the ends of the intervals it overlaps,
+/// and the primes in each part are counted a word at a time, so this is much faster than grouping the primes from [`below`](crate::below).
+///
+/// # Panics
+///
+/// Panics if `bucket_width` is 0, or if there are more intervals than a `usize` can count, which is possible on 32-bit targets.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(primes::prime_histogram(100, 10), [4, 4, 2, 2, 3, 2, 2, 3, 2, 1]);
+/// // The last interval is [100, 101), which holds 101.
+/// assert_eq!(primes::prime_histogram(101, 10), [4, 4, 2, 2, 3, 2, 2, 3, 2, 1, 0]);
+/// assert_eq!(primes::prime_histogram(102, 10), [4, 4, 2, 2, 3, 2, 2, 3, 2, 1, 1]);
+/// assert_eq!(primes::prime_histogram(0, 10), []);
+/// assert_eq!(primes::prime_histogram(1_000, 1_000_000), [168]);
+/// assert_eq!(primes::prime_histogram(6, 1), [0, 0, 1, 1, 0, 1]);
+///
+/// // The counts add up to the primes below the limit.
+/// for limit in [1, 2, 3, 7, 8, 1_000, 65_537, 1_000_000, 10_000_000] {
+///     let below = primes::strictly_below(limit).count() as u64;
+///     for width in [1, 7, 30, 1_000, 123_457, limit] {
+///         assert_eq!(primes::prime_histogram(limit, width).iter().sum::<u64>(), below);
+///     }
+/// }
+///
+/// // Against grouping the primes, with widths that don't line up with the segments.
+/// let all: Vec<_> = primes::strictly_below(3_000_000).collect();
+/// for width in [1_000, 65_536, 99_991, 2_999_999] {
+///     let mut expected = vec![0; 3_000_000usize.div_ceil(width)];
+///     for &p in &all {
+///         expected[p as usize / width] += 1;
+///     }
+///     assert_eq!(primes::prime_histogram(3_000_000, width as u64), expected);
+/// }
+/// ```
+pub fn prime_histogram(limit: u64, bucket_width: u64) -> Vec<u64> {
+    assert!(bucket_width > 0, "the bucket width must be positive");
+    let buckets =
+        usize::try_from(limit.div_ceil(bucket_width)).expect("too many buckets to store in a Vec");
+    let mut histogram = vec![0; buckets];
+    for p in WHEEL_PRIMES.into_iter().take_while(|&p| p < limit) {
+        histogram[as_index(p / bucket_width)] += 1;
+    }
+    if limit > 7 {
+        let mut segments = Segments::new(limit - 1);
+        // The segments follow on from each other, starting from 0.
+        let mut low = 0;
+        while let Some(sieve) = segments.next_segment() {
+            let high = sieve.end().saturating_add(1).min(limit);
+            while low < high {
+                let bucket = low / bucket_width;
+                let end = (bucket + 1).saturating_mul(bucket_width).min(high);
+                histogram[as_index(bucket)] += sieve.count_between(low, end);
+                low = end;
+            }
+        }
+    }
+    histogram
+}
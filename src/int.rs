@@ -0,0 +1,226 @@
+//! Yielding primes as integer types other than `u64`, checked to fit once up front rather than cast one at a time.
+
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+
+use crate::sieve::as_index;
+use crate::{below, between, first, Error, Primes, PrimesCount, PRIME_COUNT_U64};
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// An unsigned integer type that primes can be yielded as, by [`below_as`], [`between_as`] and [`first_as`].
+///
+/// Implemented for `u32`, `u64`, `usize` and `u128`, and sealed, so that the range checks can rely on every implementation.
+/// The sieve always runs in `u64`, so `u128` only widens the primes it finds.
+pub trait PrimeInt: sealed::Sealed + Copy {
+    /// The largest value of the type, or `u64::MAX` if that is larger.
+    #[doc(hidden)]
+    const MAX_U64: u64;
+    /// The number of primes that fit in the type, or [`PRIME_COUNT_U64`] if that is larger.
+    #[doc(hidden)]
+    const PRIME_COUNT: u64;
+
+    /// Converts a prime that is known to fit.
+    #[doc(hidden)]
+    fn from_prime(p: u64) -> Self;
+}
+
+impl sealed::Sealed for u32 {}
+impl PrimeInt for u32 {
+    const MAX_U64: u64 = u32::MAX as u64;
+    const PRIME_COUNT: u64 = 203_280_221;
+
+    #[inline(always)]
+    #[allow(clippy::cast_possible_truncation)]
+    fn from_prime(p: u64) -> Self {
+        debug_assert!(p <= Self::MAX_U64);
+        p as u32
+    }
+}
+
+impl sealed::Sealed for u64 {}
+impl PrimeInt for u64 {
+    const MAX_U64: u64 = u64::MAX;
+    const PRIME_COUNT: u64 = PRIME_COUNT_U64;
+
+    #[inline(always)]
+    fn from_prime(p: u64) -> Self {
+        p
+    }
+}
+
+impl sealed::Sealed for usize {}
+impl PrimeInt for usize {
+    #[cfg(target_pointer_width = "16")]
+    const MAX_U64: u64 = u16::MAX as u64;
+    #[cfg(target_pointer_width = "16")]
+    const PRIME_COUNT: u64 = 6_542;
+    #[cfg(target_pointer_width = "32")]
+    const MAX_U64: u64 = u32::MAX as u64;
+    #[cfg(target_pointer_width = "32")]
+    const PRIME_COUNT: u64 = u32::PRIME_COUNT;
+    #[cfg(target_pointer_width = "64")]
+    const MAX_U64: u64 = u64::MAX;
+    #[cfg(target_pointer_width = "64")]
+    const PRIME_COUNT: u64 = PRIME_COUNT_U64;
+
+    #[inline(always)]
+    fn from_prime(p: u64) -> Self {
+        as_index(p)
+    }
+}
+
+impl sealed::Sealed for u128 {}
+impl PrimeInt for u128 {
+    const MAX_U64: u64 = u64::MAX;
+    const PRIME_COUNT: u64 = PRIME_COUNT_U64;
+
+    #[inline(always)]
+    fn from_prime(p: u64) -> Self {
+        u128::from(p)
+    }
+}
+
+/// Like [`below`], but yields the primes as `T`.
+///
+/// # Errors
+///
+/// Returns [`Error::OutOfRange`] if `n` doesn't fit in `T`, rather than an iterator whose primes might not.
+///
+/// # Examples
+///
+/// ```
+/// use primes::Error;
+///
+/// let small: Vec<u32> = primes::below_as(30).unwrap().collect();
+/// assert_eq!(small, [2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+///
+/// for n in [0, 2, 1_000, 1_000_003] {
+///     let expected = primes::below_vec(n);
+///     assert!(primes::below_as::<u32>(n).unwrap().map(u64::from).eq(expected.iter().copied()));
+///     assert!(primes::below_as::<usize>(n).unwrap().map(|p| p as u64).eq(expected.iter().copied()));
+///     assert!(primes::below_as::<u128>(n).unwrap().eq(expected.iter().map(|&p| u128::from(p))));
+///     assert_eq!(primes::below_as::<u64>(n).unwrap().count(), expected.len());
+/// }
+///
+/// // The largest u32 prime, right up to the limit.
+/// assert_eq!(primes::below_as::<u32>(u32::MAX.into()).unwrap().skip_to(4_294_967_000).last(), Some(4_294_967_291));
+/// assert_eq!(primes::below_as::<u32>(u64::from(u32::MAX) + 1).unwrap_err(), Error::OutOfRange);
+/// ```
+pub fn below_as<T: PrimeInt>(n: u64) -> Result<Cast<Primes, T>, Error> {
+    if n > T::MAX_U64 {
+        return Err(Error::OutOfRange);
+    }
+    Ok(Cast::new(below(n)))
+}
+
+/// Like [`between`], but yields the primes as `T`.
+///
+/// # Errors
+///
+/// Returns [`Error::OutOfRange`] if `high` doesn't fit in `T`.
+///
+/// # Examples
+///
+/// ```
+/// let window: Vec<u32> = primes::between_as(100, 130).unwrap().collect();
+/// assert_eq!(window, [101, 103, 107, 109, 113, 127]);
+/// assert!(primes::between_as::<u32>(0, 1 << 32).is_err());
+/// ```
+pub fn between_as<T: PrimeInt>(low: u64, high: u64) -> Result<Cast<Primes, T>, Error> {
+    if high > T::MAX_U64 {
+        return Err(Error::OutOfRange);
+    }
+    Ok(Cast::new(between(low, high)))
+}
+
+/// Like [`first`], but yields the primes as `T`.
+///
+/// # Errors
+///
+/// Returns [`Error::OutOfRange`] if the `n`th prime doesn't fit in `T`.
+/// There are 203,280,221 primes that fit in a `u32`.
+///
+/// # Examples
+///
+/// ```
+/// use primes::Error;
+///
+/// let first: Vec<usize> = primes::first_as(5).unwrap().collect();
+/// assert_eq!(first, [2, 3, 5, 7, 11]);
+///
+/// let expected = primes::first_vec(100_000);
+/// assert!(primes::first_as::<u32>(100_000).unwrap().map(u64::from).eq(expected));
+///
+/// assert!(primes::first_as::<u32>(203_280_221).is_ok());
+/// assert_eq!(primes::first_as::<u32>(203_280_222).unwrap_err(), Error::OutOfRange);
+/// assert!(primes::first_as::<u64>(primes::PRIME_COUNT_U64).is_ok());
+/// ```
+pub fn first_as<T: PrimeInt>(n: u64) -> Result<Cast<PrimesCount, T>, Error> {
+    if n > T::PRIME_COUNT {
+        return Err(Error::OutOfRange);
+    }
+    Ok(Cast::new(first(n)))
+}
+
+/// An iterator over primes as another integer type, created by [`below_as`], [`between_as`] or [`first_as`].
+///
+/// It is exact-sized when the underlying iterator is.
+#[derive(Clone, Debug)]
+pub struct Cast<I, T> {
+    primes: I,
+    int: PhantomData<T>,
+}
+
+impl<I, T> Cast<I, T> {
+    fn new(primes: I) -> Self {
+        Cast {
+            primes,
+            int: PhantomData,
+        }
+    }
+}
+
+impl<T> Cast<Primes, T> {
+    /// Moves the iterator forward to the first prime at least `value`, as [`Primes::skip_to`] does,
+    /// and returns it for chaining.
+    pub fn skip_to(mut self, value: u64) -> Self {
+        self.primes.skip_to(value);
+        self
+    }
+}
+
+impl<I: Iterator<Item = u64>, T: PrimeInt> Iterator for Cast<I, T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        self.primes.next().map(T::from_prime)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.primes.size_hint()
+    }
+
+    fn nth(&mut self, n: usize) -> Option<T> {
+        self.primes.nth(n).map(T::from_prime)
+    }
+
+    fn count(self) -> usize {
+        self.primes.count()
+    }
+
+    fn last(self) -> Option<T> {
+        self.primes.last().map(T::from_prime)
+    }
+
+    fn fold<B, F: FnMut(B, T) -> B>(self, init: B, mut f: F) -> B {
+        self.primes.fold(init, |acc, p| f(acc, T::from_prime(p)))
+    }
+}
+
+impl<I: ExactSizeIterator<Item = u64>, T: PrimeInt> ExactSizeIterator for Cast<I, T> {}
+
+impl<I: FusedIterator<Item = u64>, T: PrimeInt> FusedIterator for Cast<I, T> {}
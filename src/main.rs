@@ -1,6 +1,305 @@
-use std::time::Instant;
+//! The `primes` command line tool. Run `primes help` for usage.
 
-fn main() {
+use std::env;
+use std::fs::File;
+use std::io::{self, ErrorKind, IsTerminal, Write};
+use std::ops::RangeBounds;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+use primes::{FactorizationFormat, OutputFormat, Progress};
+
+const USAGE: &str = "\
+usage: primes <command> [<args>]
+
+commands:
+  nth <n>                    print the nth prime, counting 2 as the 1st
+  list --below <n>           print every prime less than or equal to n
+  list --first <n>           print the first n primes
+  factor <n> [<n>...]        print the prime factorization of each n
+  isprime <n> [<n>...]       print whether each n is prime
+  count --below <n>          print how many primes are less than or equal to n
+  digest --below <n>         print the digest of the primes less than or equal to n,
+                             for checking a run against another
+  bench                      run the benchmarks
+  help                       print this message
+
+options for list and factor:
+  --format <format>          lines (the default), csv or jsonl, or for list only,
+                             binary (little-endian u64s) or delta (varint gaps)
+  --output <path>            write to a file rather than stdout";
+
+/// Why a command failed, which decides what is printed and the exit code.
+enum Failure {
+    /// The arguments don't make sense, so the usage is printed too.
+    Usage(String),
+    /// The arguments parsed, but there is no answer for them.
+    Input(String),
+    Io(io::Error),
+}
+
+impl From<io::Error> for Failure {
+    fn from(e: io::Error) -> Self {
+        Failure::Io(e)
+    }
+}
+
+impl From<primes::Error> for Failure {
+    fn from(e: primes::Error) -> Self {
+        Failure::Input(e.to_string())
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Failure::Usage(message)) => {
+            eprintln!("error: {message}\n\n{USAGE}");
+            ExitCode::from(2)
+        }
+        Err(Failure::Input(message)) => {
+            eprintln!("error: {message}");
+            ExitCode::FAILURE
+        }
+        // The reader went away, as `primes list --below 1000000 | head` does, which isn't an error.
+        Err(Failure::Io(e)) if e.kind() == ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(Failure::Io(e)) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<(), Failure> {
+    let Some((command, args)) = args.split_first() else {
+        return Err(Failure::Usage("no command given".to_string()));
+    };
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let mut out = io::stdout().lock();
+    match (command.as_str(), args.as_slice()) {
+        ("nth", [n]) => writeln!(out, "{}", primes::try_nth(parse(n)?)?)?,
+        ("list", args) => {
+            let mut flags = Flags::new(args, &["--below", "--first", "--format", "--output"])?;
+            let metered = io::stderr().is_terminal();
+            let primes: Box<dyn Iterator<Item = u64>> =
+                match (flags.take("--below"), flags.take("--first")) {
+                    (Some(n), None) => {
+                        let primes = primes::below(parse(n)?);
+                        if metered {
+                            Box::new(primes.with_progress(meter(sieved)))
+                        } else {
+                            Box::new(primes)
+                        }
+                    }
+                    (None, Some(n)) => {
+                        let n = parse(n)?;
+                        let primes = primes::first(n);
+                        if metered {
+                            Box::new(primes.with_progress(meter(move |progress| {
+                                progress.yielded as f64 / n as f64
+                            })))
+                        } else {
+                            Box::new(primes)
+                        }
+                    }
+                    _ => {
+                        return Err(Failure::Usage(
+                            "`list` takes one of `--below` and `--first`".to_string(),
+                        ))
+                    }
+                };
+            let format = match flags.take("--format").unwrap_or("lines") {
+                "lines" => OutputFormat::Text,
+                "csv" => OutputFormat::Csv,
+                "jsonl" => OutputFormat::JsonLines,
+                "binary" => OutputFormat::BinaryLe,
+                "delta" => OutputFormat::BinaryDelta,
+                format => {
+                    return Err(Failure::Usage(format!(
+                        "unknown format `{format}` for `list`"
+                    )))
+                }
+            };
+            flags.positional(0..=0)?;
+            match flags.take("--output") {
+                Some(path) => primes::write_primes(File::create(path)?, primes, format)?,
+                None => primes::write_primes(&mut out, primes, format)?,
+            };
+        }
+        ("factor", args) => {
+            let mut flags = Flags::new(args, &["--format", "--output"])?;
+            let format = match flags.take("--format").unwrap_or("lines") {
+                "lines" => FactorizationFormat::Text,
+                "csv" => FactorizationFormat::Csv,
+                "jsonl" => FactorizationFormat::JsonLines,
+                format => {
+                    return Err(Failure::Usage(format!(
+                        "unknown format `{format}` for `factor`"
+                    )))
+                }
+            };
+            let output = flags.take("--output");
+            let ns = flags.positional(1..)?;
+            // Every input is checked before anything is written.
+            let factorizations = ns
+                .iter()
+                .map(|n| {
+                    let n = parse(n)?;
+                    Ok((n, primes::try_divisors(n)?))
+                })
+                .collect::<Result<Vec<_>, Failure>>()?;
+            match output {
+                Some(path) => {
+                    primes::write_factorizations(File::create(path)?, factorizations, format)?
+                }
+                None => primes::write_factorizations(&mut out, factorizations, format)?,
+            };
+        }
+        ("isprime", ns) if !ns.is_empty() => {
+            let ns = ns.iter().map(|n| parse(n)).collect::<Result<Vec<_>, _>>()?;
+            for n in ns {
+                let verdict = if primes::is_prime(n) {
+                    "prime"
+                } else {
+                    "not prime"
+                };
+                writeln!(out, "{n} is {verdict}")?;
+            }
+        }
+        ("count", ["--below", n]) => {
+            let primes = primes::below(parse(n)?);
+            let count = if io::stderr().is_terminal() {
+                primes.with_progress(meter(sieved)).count()
+            } else {
+                primes.count()
+            };
+            writeln!(out, "{count}")?;
+        }
+        ("digest", ["--below", n]) => {
+            let primes = primes::below(parse(n)?);
+            let digest = if io::stderr().is_terminal() {
+                primes::digest_of(primes.with_progress(meter(sieved)))
+            } else {
+                primes::digest_of(primes)
+            };
+            writeln!(out, "{digest}")?;
+        }
+        ("bench", []) => bench(&mut out)?,
+        ("help" | "--help" | "-h", []) => writeln!(out, "{USAGE}")?,
+        ("nth" | "isprime" | "count" | "digest" | "bench" | "help", _) => {
+            return Err(Failure::Usage(format!("wrong arguments for `{command}`")));
+        }
+        _ => return Err(Failure::Usage(format!("unknown command `{command}`"))),
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// The arguments to a command, split into `--flag value` pairs and the positional arguments around them.
+struct Flags<'a> {
+    flags: Vec<(&'a str, &'a str)>,
+    positional: Vec<&'a str>,
+}
+
+impl<'a> Flags<'a> {
+    /// Splits `args`, rejecting any flag not in `allowed`, given twice, or without a value.
+    fn new(args: &[&'a str], allowed: &[&str]) -> Result<Self, Failure> {
+        let mut flags: Vec<(&str, &str)> = Vec::new();
+        let mut positional = Vec::new();
+        let mut args = args.iter();
+        while let Some(&arg) = args.next() {
+            if !arg.starts_with("--") {
+                positional.push(arg);
+            } else if !allowed.contains(&arg) {
+                return Err(Failure::Usage(format!("unknown option `{arg}`")));
+            } else if flags.iter().any(|&(flag, _)| flag == arg) {
+                return Err(Failure::Usage(format!("`{arg}` given more than once")));
+            } else {
+                let value = args
+                    .next()
+                    .ok_or_else(|| Failure::Usage(format!("`{arg}` needs a value")))?;
+                flags.push((arg, value));
+            }
+        }
+        Ok(Flags { flags, positional })
+    }
+
+    /// Returns the value given for `flag`, if any.
+    fn take(&mut self, flag: &str) -> Option<&'a str> {
+        let i = self.flags.iter().position(|&(f, _)| f == flag)?;
+        Some(self.flags.swap_remove(i).1)
+    }
+
+    /// Returns the positional arguments, checking that there are as many as `count` allows.
+    fn positional(&self, count: impl RangeBounds<usize>) -> Result<&[&'a str], Failure> {
+        if count.contains(&self.positional.len()) {
+            Ok(&self.positional)
+        } else {
+            Err(Failure::Usage("wrong number of arguments".to_string()))
+        }
+    }
+}
+
+/// Returns how much of the way to its limit the sieve is.
+fn sieved(progress: Progress) -> f64 {
+    progress.position as f64 / progress.limit.max(1) as f64
+}
+
+/// Returns a progress callback that shows a percentage and the time left on stderr, given how much is done.
+///
+/// Nothing is shown for the first half second, so quick runs don't flash up a line,
+/// and the line is erased when the callback is dropped, so it doesn't mix with the output.
+fn meter(done: impl Fn(Progress) -> f64) -> impl FnMut(Progress) {
+    let mut meter = Meter {
+        start: Instant::now(),
+        drawn: None,
+    };
+    move |progress| meter.draw(done(progress))
+}
+
+/// The state behind [`meter`].
+struct Meter {
+    start: Instant,
+    drawn: Option<Instant>,
+}
+
+impl Meter {
+    fn draw(&mut self, done: f64) {
+        let now = Instant::now();
+        let elapsed = now - self.start;
+        let recent = self
+            .drawn
+            .is_some_and(|drawn| now - drawn < Duration::from_millis(100));
+        if elapsed < Duration::from_millis(500) || recent || done <= 0.0 {
+            return;
+        }
+        self.drawn = Some(now);
+        let left = elapsed.as_secs_f64() * (1.0 - done) / done;
+        eprint!("\r\x1b[K{:.1}% done, about {left:.0}s left", done * 100.0);
+    }
+}
+
+impl Drop for Meter {
+    fn drop(&mut self) {
+        if self.drawn.is_some() {
+            eprint!("\r\x1b[K");
+        }
+    }
+}
+
+/// Parses a non-negative integer, which may have `_` separators.
+fn parse(s: &str) -> Result<u64, Failure> {
+    s.replace('_', "").parse().map_err(|_| {
+        Failure::Usage(format!(
+            "`{s}` is not a non-negative integer that fits in 64 bits"
+        ))
+    })
+}
+
+/// Times the iterators against known answers, failing on the first wrong one.
+fn bench(out: &mut impl Write) -> Result<(), Failure> {
+    let wrong = |what: String| Failure::Input(format!("{what} gave the wrong answer"));
     for (n, prime) in [
         (5, 11),
         (10, 29),
@@ -15,15 +314,150 @@ fn main() {
         (100_000_000, 2_038_074_743),
     ] {
         let now = Instant::now();
-        let result = primes::nth(n).unwrap();
+        let result = primes::nth(n);
         let time = now.elapsed().as_nanos() as f64 / 1_000_000.;
-        if result == prime {
-            println!("{n}th succeeded in {time}ms");
-        } else {
-            println!("{n}th failed");
-            println!("Expected: {}", prime);
-            println!("Got: {}", result);
-            return;
+        if result != Some(prime) {
+            return Err(wrong(format!("nth({n})")));
         }
+        writeln!(out, "{n}th succeeded in {time}ms")?;
+    }
+    for n in [1_000_000, 10_000_000, 100_000_000] {
+        let now = Instant::now();
+        let collected: Vec<_> = primes::below(n).collect();
+        let collect_time = now.elapsed().as_nanos() as f64 / 1_000_000.;
+        let now = Instant::now();
+        let vec = primes::below_vec(n);
+        let vec_time = now.elapsed().as_nanos() as f64 / 1_000_000.;
+        if collected != vec {
+            return Err(wrong(format!("below_vec({n})")));
+        }
+        writeln!(
+            out,
+            "below({n}) collected in {collect_time}ms, below_vec({n}) in {vec_time}ms"
+        )?;
+    }
+    #[cfg(feature = "rayon")]
+    for threads in [1, 2, 4, 8] {
+        let now = Instant::now();
+        let count = primes::below_parallel_on(1_000_000_000, threads).len();
+        let time = now.elapsed().as_nanos() as f64 / 1_000_000.;
+        if count != 50_847_534 {
+            return Err(wrong(format!("below_parallel_on with {threads} threads")));
+        }
+        writeln!(
+            out,
+            "below_parallel_on(10^9) on {threads} threads in {time}ms, with {} cores available",
+            std::thread::available_parallelism().map_or(1, usize::from)
+        )?;
+    }
+    for size in [
+        1_000, 4_000, 8_000, 16_000, 32_000, 64_000, 256_000, 1_000_000,
+    ] {
+        let now = Instant::now();
+        let count = primes::Builder::new()
+            .segment_size(size)
+            .limit(1_000_000_000)
+            .build()
+            .count();
+        let time = now.elapsed().as_nanos() as f64 / 1_000_000.;
+        if count != 50_847_534 {
+            return Err(wrong(format!("segment size {size}")));
+        }
+        writeln!(
+            out,
+            "segment size {size} bytes counted below 10^9 in {time}ms"
+        )?;
+    }
+    for wheel in [primes::WheelSize::W30, primes::WheelSize::W210] {
+        let now = Instant::now();
+        let sum = primes::Builder::new()
+            .wheel(wheel)
+            .limit(1_000_000_000)
+            .build()
+            .fold(0u64, |sum, p| sum.wrapping_add(p));
+        let time = now.elapsed().as_nanos() as f64 / 1_000_000.;
+        if sum != 24_739_512_092_254_535 {
+            return Err(wrong(format!("{wheel:?}")));
+        }
+        writeln!(out, "{wheel:?} iterated below 10^9 in {time}ms")?;
+    }
+    for backend in [primes::Backend::Eratosthenes, primes::Backend::Atkin] {
+        let now = Instant::now();
+        let count = primes::Builder::new()
+            .backend(backend)
+            .limit(100_000_000)
+            .build()
+            .count();
+        let time = now.elapsed().as_nanos() as f64 / 1_000_000.;
+        if count != 5_761_455 {
+            return Err(wrong(format!("{backend:?}")));
+        }
+        writeln!(out, "{backend:?} counted below 10^8 in {time}ms")?;
+    }
+    let now = Instant::now();
+    let count = primes::between(100_000_000_000_000, 100_001_000_000_000).count();
+    let time = now.elapsed().as_nanos() as f64 / 1_000_000.;
+    if count != 31_019_409 {
+        return Err(wrong("between".to_string()));
+    }
+    writeln!(out, "counted between 10^14 and 10^14 + 10^9 in {time}ms")?;
+    // Below 2^48, `divisors` shares a table too, so reusing one only pays off past it.
+    for (low, count) in [(1 << 32, 1_000_000), (1 << 50, 1_000)] {
+        let checksum = |factorizations: &mut dyn FnMut(u64) -> primes::Divisors| {
+            let now = Instant::now();
+            let sum = (low..low + count)
+                .flat_map(&mut *factorizations)
+                .fold(0u64, |sum, (p, k)| sum.wrapping_add(p * k));
+            (sum, now.elapsed().as_nanos() as f64 / 1_000_000.)
+        };
+        let (standalone, standalone_time) = checksum(&mut primes::divisors);
+        let mut factorizer = primes::Factorizer::new();
+        let (reused, reused_time) = checksum(&mut |n| factorizer.factor(n));
+        if standalone != reused {
+            return Err(wrong(format!("Factorizer from {low}")));
+        }
+        writeln!(
+            out,
+            "factorized {count} numbers from {low} in {standalone_time}ms with divisors, {reused_time}ms with a Factorizer"
+        )?;
+    }
+    let now = Instant::now();
+    let mut grouped = vec![0u64; 1_000];
+    for p in primes::strictly_below(1_000_000_000) {
+        grouped[(p / 1_000_000) as usize] += 1;
+    }
+    let grouped_time = now.elapsed().as_nanos() as f64 / 1_000_000.;
+    let now = Instant::now();
+    let histogram = primes::prime_histogram(1_000_000_000, 1_000_000);
+    let histogram_time = now.elapsed().as_nanos() as f64 / 1_000_000.;
+    if histogram != grouped {
+        return Err(wrong("prime_histogram".to_string()));
+    }
+    writeln!(
+        out,
+        "counted the primes below 10^9 in intervals of 10^6 in {grouped_time}ms grouping, {histogram_time}ms with prime_histogram"
+    )?;
+    // A million candidates scattered over 10^7 numbers from 10^12, dense enough to sieve.
+    let mut seed = 1u64;
+    let candidates: Vec<_> = (0..1_000_000)
+        .map(|_| {
+            seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+            1_000_000_000_000 + (seed >> 32) % 10_000_000
+        })
+        .collect();
+    let now = Instant::now();
+    let each: Vec<_> = candidates.iter().map(|&n| primes::is_prime(n)).collect();
+    let each_time = now.elapsed().as_nanos() as f64 / 1_000_000.;
+    let now = Instant::now();
+    let classified = primes::classify_primes(&candidates);
+    let classify_time = now.elapsed().as_nanos() as f64 / 1_000_000.;
+    if classified != each {
+        return Err(wrong("classify_primes".to_string()));
     }
+    writeln!(
+        out,
+        "classified {} candidates from 10^12 in {each_time}ms with is_prime, {classify_time}ms with classify_primes",
+        candidates.len()
+    )?;
+    Ok(())
 }
@@ -0,0 +1,588 @@
+//! Modular arithmetic that can't overflow.
+
+/// Returns `a * b mod m`, multiplying in `u128` so that it never overflows.
+///
+/// `a` and `b` don't need to be reduced mod `m` first, and everything is 0 mod 1.
+///
+/// # Panics
+///
+/// Panics if `m` is 0.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(primes::mul_mod(7, 8, 10), 6);
+/// assert_eq!(primes::mul_mod(u64::MAX, u64::MAX, u64::MAX - 1), 1);
+/// assert_eq!(primes::mul_mod(123, 456, 1), 0);
+///
+/// let mut seed = 1u64;
+/// let mut random = || {
+///     seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+///     seed
+/// };
+/// for _ in 0..1_000 {
+///     let (a, b, m) = (random(), random(), random() >> (random() % 64) | 1);
+///     let expected = u128::from(a) * u128::from(b) % u128::from(m);
+///     assert_eq!(u128::from(primes::mul_mod(a, b, m)), expected);
+///     assert_eq!(primes::mul_mod(a % m, b % m, m), primes::mul_mod(a, b, m));
+/// }
+/// ```
+// The remainder is less than `m`, so casting it back down never truncates.
+#[allow(clippy::cast_possible_truncation)]
+pub fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    (a as u128 * b as u128 % m as u128) as u64
+}
+
+/// Returns `base ^ exp mod m`, by repeated squaring.
+///
+/// `0 ^ 0` is 1, and everything is 0 mod 1.
+///
+/// # Panics
+///
+/// Panics if `m` is 0.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(primes::pow_mod(3, 4, 7), 4);
+/// assert_eq!(primes::pow_mod(0, 0, 7), 1);
+/// assert_eq!(primes::pow_mod(5, 0, 1), 0);
+/// assert_eq!(primes::pow_mod(10, 3, 7), primes::pow_mod(3, 3, 7));
+///
+/// let mut seed = 1u64;
+/// let mut random = || {
+///     seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+///     seed
+/// };
+/// for _ in 0..200 {
+///     let (base, exp, m) = (random(), random() % 100, random() | 1);
+///     let repeated = (0..exp).fold(1 % m, |x, _| primes::mul_mod(x, base, m));
+///     assert_eq!(primes::pow_mod(base, exp, m), repeated);
+/// }
+///
+/// // Fermat's little theorem holds for every prime, and below 10,000, for no composite with all of the bases 2 to 9.
+/// for n in 11..10_000u64 {
+///     let fermat = (2..10).all(|a| primes::pow_mod(a, n - 1, n) == 1);
+///     assert_eq!(fermat, primes::is_prime(n));
+/// }
+/// assert_eq!(primes::pow_mod(2, 18_446_744_073_709_551_557 - 1, 18_446_744_073_709_551_557), 1);
+/// ```
+pub fn pow_mod(mut base: u64, mut exp: u64, m: u64) -> u64 {
+    let mut result = 1 % m;
+    base %= m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+/// Returns the inverse of `a` mod `m`, the `x < m` with `a * x mod m = 1`,
+/// or `None` if there isn't one because `a` and `m` share a factor.
+///
+/// 0 has no inverse, except mod 1, where everything is 0 and so is its own inverse.
+///
+/// # Panics
+///
+/// Panics if `m` is 0.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(primes::inv_mod(3, 7), Some(5));
+/// assert_eq!(primes::inv_mod(10, 7), Some(5));
+/// assert_eq!(primes::inv_mod(4, 6), None);
+/// assert_eq!(primes::inv_mod(0, 7), None);
+/// assert_eq!(primes::inv_mod(0, 1), Some(0));
+/// assert_eq!(primes::inv_mod(u64::MAX - 1, u64::MAX), Some(u64::MAX - 1));
+///
+/// for m in 1..300 {
+///     for a in 0..2 * m {
+///         let gcd = (1..=m).rev().find(|d| a % d == 0 && m % d == 0).unwrap();
+///         match primes::inv_mod(a, m) {
+///             Some(x) => assert!(x < m && primes::mul_mod(a, x, m) == 1 % m),
+///             None => assert_ne!(gcd, 1),
+///         }
+///     }
+/// }
+///
+/// // By Fermat's little theorem, the inverse mod a prime is also a power.
+/// let p = 18_446_744_073_709_551_557;
+/// for a in [2, 3, 1_000_000_007, p - 1] {
+///     assert_eq!(primes::inv_mod(a, p), Some(primes::pow_mod(a, p - 2, p)));
+/// }
+/// ```
+pub fn inv_mod(a: u64, m: u64) -> Option<u64> {
+    assert!(m != 0, "inverse mod 0");
+    // The extended Euclidean algorithm, keeping `r = x * a mod m` for each remainder.
+    let (mut r0, mut r1) = (i128::from(m), i128::from(a % m));
+    let (mut x0, mut x1) = (0i128, 1i128);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (x0, x1) = (x1, x0 - q * x1);
+    }
+    if r0 != 1 {
+        return None;
+    }
+    u64::try_from(x0.rem_euclid(i128::from(m))).ok()
+}
+
+/// Returns the Jacobi symbol `(a / n)`, which is 0, 1 or -1, or `None` if `n` is even, since it is only defined for odd `n`.
+///
+/// For a prime `n`, this is the Legendre symbol: 0 if `n` divides `a`, 1 if `a` is a nonzero square mod `n`, and -1 otherwise.
+/// For composite `n`, it is the product of the Legendre symbols for the prime factors of `n`,
+/// so -1 still means that `a` isn't a square mod `n`, but 1 doesn't mean that it is.
+/// `(a / 1)` is 1 for every `a`.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(primes::jacobi(2, 7), Some(1));
+/// assert_eq!(primes::jacobi(3, 7), Some(-1));
+/// assert_eq!(primes::jacobi(14, 7), Some(0));
+/// assert_eq!(primes::jacobi(-1, 7), Some(-1));
+/// assert_eq!(primes::jacobi(-1, 13), Some(1));
+/// assert_eq!(primes::jacobi(2, 15), Some(1));
+/// assert_eq!(primes::jacobi(5, 8), None);
+/// assert_eq!(primes::jacobi(5, 0), None);
+/// for a in [i64::MIN, -1, 0, 1, i64::MAX] {
+///     assert_eq!(primes::jacobi(a, 1), Some(1));
+/// }
+///
+/// // The product of the Legendre symbols of the prime factors.
+/// for n in (1..2_000u64).step_by(2) {
+///     for a in -50..50i64 {
+///         let product = primes::divisors(n)
+///             .map(|(p, k)| primes::legendre(a.rem_euclid(p as i64) as u64, p).unwrap().pow(k as u32))
+///             .product::<i8>();
+///         assert_eq!(primes::jacobi(a, n), Some(product));
+///         // Multiplicative in the top argument.
+///         let b = a + 3;
+///         assert_eq!(primes::jacobi(a * b, n), Some(primes::jacobi(a, n).unwrap() * primes::jacobi(b, n).unwrap()));
+///     }
+/// }
+/// assert_eq!(primes::jacobi(i64::MIN, 18_446_744_073_709_551_557), Some(-1));
+/// assert_eq!(primes::legendre(u64::MAX, 18_446_744_073_709_551_557), Some(-1));
+/// ```
+pub fn jacobi(a: i64, n: u64) -> Option<i8> {
+    if n.is_multiple_of(2) {
+        return None;
+    }
+    let a = u64::try_from(i128::from(a).rem_euclid(i128::from(n))).ok()?;
+    Some(jacobi_unsigned(a, n))
+}
+
+/// Returns the Jacobi symbol `(a / n)` for odd `n`.
+fn jacobi_unsigned(mut a: u64, mut n: u64) -> i8 {
+    a %= n;
+    let mut symbol = 1;
+    while a != 0 {
+        // (2 / n) is -1 exactly when n is 3 or 5 mod 8.
+        let twos = a.trailing_zeros();
+        a >>= twos;
+        if twos % 2 == 1 && matches!(n % 8, 3 | 5) {
+            symbol = -symbol;
+        }
+        // Quadratic reciprocity flips the sign when both are 3 mod 4.
+        if a % 4 == 3 && n % 4 == 3 {
+            symbol = -symbol;
+        }
+        (a, n) = (n % a, a);
+    }
+    if n == 1 {
+        symbol
+    } else {
+        0
+    }
+}
+
+/// Returns the Legendre symbol `(a / p)`: 0 if `p` divides `a`, 1 if `a` is a nonzero square mod `p`, and -1 otherwise,
+/// or `None` if `p` isn't an odd prime.
+///
+/// This is the [Jacobi symbol](jacobi) for a prime `p`, which is checked with [`is_prime`](crate::is_prime).
+/// Debug builds also check the answer against Euler's criterion, `a ^ ((p - 1) / 2) mod p`.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(primes::legendre(2, 7), Some(1));
+/// assert_eq!(primes::legendre(3, 7), Some(-1));
+/// assert_eq!(primes::legendre(7, 7), Some(0));
+/// assert_eq!(primes::legendre(2, 15), None);
+/// assert_eq!(primes::legendre(1, 2), None);
+///
+/// for p in primes::between(3, 2_000) {
+///     let squares: std::collections::HashSet<_> = (1..p).map(|x| x * x % p).collect();
+///     for a in 0..2 * p {
+///         let expected = match a % p {
+///             0 => 0,
+///             r if squares.contains(&r) => 1,
+///             _ => -1,
+///         };
+///         assert_eq!(primes::legendre(a, p), Some(expected));
+///         let euler = primes::pow_mod(a, (p - 1) / 2, p);
+///         assert_eq!(euler, [p - 1, 0, 1][(expected + 1) as usize]);
+///     }
+/// }
+/// ```
+pub fn legendre(a: u64, p: u64) -> Option<i8> {
+    if p == 2 || !crate::is_prime(p) {
+        return None;
+    }
+    let symbol = jacobi_unsigned(a, p);
+    debug_assert_eq!(
+        pow_mod(a, (p - 1) / 2, p),
+        match symbol {
+            0 => 0,
+            1 => 1,
+            _ => p - 1,
+        }
+    );
+    Some(symbol)
+}
+
+/// Returns whether `a` is a nonzero square mod the odd prime `p`, or `None` if `p` isn't an odd prime.
+///
+/// Multiples of `p` are squares of 0, but like for the [Legendre symbol](legendre), they are not counted as residues,
+/// so this is `Some(false)` for them, matching [`quadratic_residues`].
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(primes::is_quadratic_residue(2, 7), Some(true));
+/// assert_eq!(primes::is_quadratic_residue(3, 7), Some(false));
+/// assert_eq!(primes::is_quadratic_residue(9, 7), Some(true));
+/// assert_eq!(primes::is_quadratic_residue(0, 7), Some(false));
+/// assert_eq!(primes::is_quadratic_residue(1, 9), None);
+/// assert_eq!(primes::is_quadratic_residue(1, 2), None);
+///
+/// for p in primes::between(3, 300) {
+///     let squares: std::collections::HashSet<_> = (1..p).map(|x| x * x % p).collect();
+///     for a in 0..2 * p {
+///         assert_eq!(primes::is_quadratic_residue(a, p), Some(squares.contains(&(a % p))));
+///     }
+/// }
+/// ```
+pub fn is_quadratic_residue(a: u64, p: u64) -> Option<bool> {
+    Some(legendre(a, p)? == 1)
+}
+
+/// Returns an iterator over the `(p - 1) / 2` nonzero squares mod the odd prime `p`, in increasing order,
+/// or over nothing if `p` isn't an odd prime.
+///
+/// Rather than sorting the squares of `1` to `(p - 1) / 2`, which would take `4 (p - 1)` bytes,
+/// each number below `p` is checked in turn, in constant memory and `O(p log p)` time.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(primes::quadratic_residues(7).collect::<Vec<_>>(), [1, 2, 4]);
+/// assert_eq!(primes::quadratic_residues(13).collect::<Vec<_>>(), [1, 3, 4, 9, 10, 12]);
+/// assert_eq!(primes::quadratic_residues(2).next(), None);
+/// assert_eq!(primes::quadratic_residues(15).next(), None);
+///
+/// for p in primes::between(3, 2_000) {
+///     let residues: Vec<_> = primes::quadratic_residues(p).collect();
+///     assert_eq!(residues.len() as u64, (p - 1) / 2);
+///     let mut squares: Vec<_> = (1..=(p - 1) / 2).map(|x| x * x % p).collect();
+///     squares.sort_unstable();
+///     assert_eq!(residues, squares);
+///     assert!((0..p).all(|a| primes::is_quadratic_residue(a, p) == Some(residues.binary_search(&a).is_ok())));
+/// }
+///
+/// let p = 1_000_000_007;
+/// let first: Vec<_> = primes::quadratic_residues(p).take(8).collect();
+/// assert_eq!(first, [1, 2, 3, 4, 6, 7, 8, 9]);
+/// ```
+pub fn quadratic_residues(p: u64) -> impl Iterator<Item = u64> {
+    let end = if legendre(0, p).is_some() { p } else { 1 };
+    (1..end).filter(move |&a| jacobi_unsigned(a, p) == 1)
+}
+
+/// Returns the smaller square root of `a` mod the prime `p`, the `r <= p / 2` with `r * r mod p = a mod p`,
+/// or `None` if `a` isn't a square mod `p`, or `p` isn't prime.
+///
+/// The other root is `p - r`, and the two are the same only for `a mod p = 0`, whose root is 0,
+/// and mod 2, where `a mod 2` is its own root.
+///
+/// Roots mod primes that are 3 mod 4 are a single power, `a ^ ((p + 1) / 4)`,
+/// and roots mod primes that are 1 mod 4 are found with the Tonelli-Shanks algorithm.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(primes::sqrt_mod(2, 7), Some(3));
+/// assert_eq!(primes::sqrt_mod(3, 7), None);
+/// assert_eq!(primes::sqrt_mod(10, 13), Some(6));
+/// assert_eq!(primes::sqrt_mod(14, 7), Some(0));
+/// assert_eq!(primes::sqrt_mod(5, 2), Some(1));
+/// assert_eq!(primes::sqrt_mod(4, 15), None);
+///
+/// for p in primes::below(500) {
+///     for a in 0..p {
+///         match primes::sqrt_mod(a, p) {
+///             Some(r) => assert!(r <= p / 2 && r * r % p == a),
+///             None => assert!((0..p).all(|x| x * x % p != a)),
+///         }
+///     }
+/// }
+///
+/// // Random residues mod large primes of both classes mod 4, up to the largest below 2^63 and 2^64.
+/// let mut seed = 1u64;
+/// let mut random = || {
+///     seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+///     seed
+/// };
+/// let large = [1_000_000_007, 998_244_353, (1 << 63) - 25, (1 << 63) - 165, 18_446_744_073_709_551_557];
+/// assert!(large.iter().any(|p| p % 4 == 1) && large.iter().any(|p| p % 4 == 3));
+/// for p in large {
+///     assert!(primes::is_prime(p));
+///     for _ in 0..100 {
+///         let x = random() % p;
+///         let a = primes::mul_mod(x, x, p);
+///         assert!([x, p - x].contains(&primes::sqrt_mod(a, p).unwrap()) || x == 0);
+///         let b = random() % p;
+///         match primes::sqrt_mod(b, p) {
+///             Some(r) => assert_eq!(primes::mul_mod(r, r, p), b),
+///             None => assert_eq!(primes::legendre(b, p), Some(-1)),
+///         }
+///     }
+/// }
+/// ```
+pub fn sqrt_mod(a: u64, p: u64) -> Option<u64> {
+    let a = a % p.max(1);
+    if p == 2 {
+        return Some(a);
+    }
+    match legendre(a, p)? {
+        0 => return Some(0),
+        -1 => return None,
+        _ => {}
+    }
+    let root = if p % 4 == 3 {
+        pow_mod(a, p.div_ceil(4), p)
+    } else {
+        // Write p - 1 = q * 2^s with q odd, and start from a guess whose error `t` has order dividing 2^(s - 1).
+        let s = (p - 1).trailing_zeros();
+        let q = (p - 1) >> s;
+        let non_residue = (2..).find(|&z| legendre(z, p) == Some(-1))?;
+        let (mut m, mut c) = (s, pow_mod(non_residue, q, p));
+        let (mut t, mut r) = (pow_mod(a, q, p), pow_mod(a, q.div_ceil(2), p));
+        // Invariants: r^2 = a * t, t has order dividing 2^(m - 1), and c has order exactly 2^m.
+        while t != 1 {
+            let mut i = 0;
+            let mut t2 = t;
+            while t2 != 1 {
+                t2 = mul_mod(t2, t2, p);
+                i += 1;
+            }
+            let b = (0..m - i - 1).fold(c, |b, _| mul_mod(b, b, p));
+            m = i;
+            c = mul_mod(b, b, p);
+            t = mul_mod(t, c, p);
+            r = mul_mod(r, b, p);
+        }
+        r
+    };
+    Some(root.min(p - root))
+}
+
+/// Returns the multiplicative order of `a` mod `n`, the least `k > 0` with `a ^ k mod n = 1 mod n`,
+/// or `None` if there isn't one because `a` and `n` share a factor, or `n` is 0.
+///
+/// Everything is 0 mod 1, which is also 1 mod 1, so the order of anything mod 1 is 1.
+///
+/// The order divides the Carmichael function `λ(n)`, which is found by factorizing `n`,
+/// so this starts from `λ(n)` and divides out each of its prime factors for as long as the power is still 1.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(primes::multiplicative_order(2, 7), Some(3));
+/// assert_eq!(primes::multiplicative_order(10, 7), Some(6));
+/// assert_eq!(primes::multiplicative_order(3, 1), Some(1));
+/// assert_eq!(primes::multiplicative_order(1, 10), Some(1));
+/// assert_eq!(primes::multiplicative_order(4, 6), None);
+/// assert_eq!(primes::multiplicative_order(0, 7), None);
+/// assert_eq!(primes::multiplicative_order(5, 0), None);
+///
+/// // The period of the decimal expansion of 1 / 17.
+/// assert_eq!(primes::multiplicative_order(10, 17), Some(16));
+/// // 5 generates the units mod the prime 10^9 + 7, and 2 only half of them.
+/// let p = 1_000_000_007;
+/// assert_eq!(primes::multiplicative_order(5, p), Some(p - 1));
+/// assert_eq!(primes::multiplicative_order(2, p), Some((p - 1) / 2));
+///
+/// let mut seed = 1u64;
+/// let mut random = || {
+///     seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+///     seed >> 44
+/// };
+/// for _ in 0..300 {
+///     let (a, n) = (random(), random().max(1));
+///     let Some(k) = primes::multiplicative_order(a, n) else {
+///         assert_eq!(primes::inv_mod(a, n), None);
+///         continue;
+///     };
+///     assert_eq!(primes::pow_mod(a, k, n), 1 % n);
+///     for (q, _) in primes::divisors(k) {
+///         assert_ne!(primes::pow_mod(a, k / q, n), 1 % n);
+///     }
+/// }
+/// for n in 1..300 {
+///     for a in 0..n {
+///         let brute = (1..=n).find(|&k| primes::pow_mod(a, k, n) == 1 % n);
+///         assert_eq!(primes::multiplicative_order(a, n), brute);
+///     }
+/// }
+/// ```
+pub fn multiplicative_order(a: u64, n: u64) -> Option<u64> {
+    if n == 0 {
+        return None;
+    }
+    inv_mod(a, n)?;
+    let mut order = crate::divisors(n).fold(1, |lambda, (p, k)| {
+        let power = match (p, k) {
+            (2, 1) => 1,
+            (2, 2) => 2,
+            (2, _) => 1 << (k - 2),
+            _ => p.pow(u32::try_from(k - 1).unwrap()) * (p - 1),
+        };
+        lambda / gcd(lambda, power) * power
+    });
+    for (q, _) in crate::divisors(order) {
+        while order % q == 0 && pow_mod(a, order / q, n) == 1 % n {
+            order /= q;
+        }
+    }
+    Some(order)
+}
+
+/// Returns the smallest `x` with `base ^ x mod modulus = target mod modulus`, or `None` if there isn't one, or `modulus` is 0.
+///
+/// `base ^ 0` is 1, so for a `target` of 1 this is 0, and everything is 0 mod 1, so mod 1 it is always 0.
+///
+/// This uses baby-step giant-step, which takes `O(sqrt(modulus))` time and memory:
+/// about `sqrt(modulus)` powers of `base` are stored in a `HashMap`, which for moduli near `u64::MAX` is tens of gigabytes.
+/// When `base` and `modulus` share a factor, so that `base` has no inverse, the shared factors are divided out first,
+/// which may find solutions even though the powers of `base` never reach 1 again, such as `2 ^ 3 mod 12 = 8`.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(primes::discrete_log(3, 13, 17), Some(4));
+/// assert_eq!(primes::discrete_log(2, 3, 7), None);
+/// assert_eq!(primes::discrete_log(5, 1, 7), Some(0));
+/// assert_eq!(primes::discrete_log(5, 8, 7), Some(0));
+/// assert_eq!(primes::discrete_log(0, 0, 7), Some(1));
+/// assert_eq!(primes::discrete_log(2, 8, 12), Some(3));
+/// assert_eq!(primes::discrete_log(2, 0, 1), Some(0));
+/// assert_eq!(primes::discrete_log(2, 0, 0), None);
+///
+/// // Against a search of every exponent, which can't repeat without cycling within `modulus` steps.
+/// for modulus in 1..80 {
+///     for base in 0..modulus {
+///         for target in 0..modulus {
+///             let brute = (0..=modulus).find(|&x| primes::pow_mod(base, x, modulus) == target);
+///             assert_eq!(primes::discrete_log(base, target, modulus), brute);
+///         }
+///     }
+/// }
+///
+/// let mut seed = 1u64;
+/// let mut random = || {
+///     seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+///     seed
+/// };
+/// for p in [1_009, 65_537, 1_000_003, 998_244_353, 1_000_000_007] {
+///     for _ in 0..10 {
+///         let (base, x) = (random() % (p - 2) + 2, random() % p);
+///         let target = primes::pow_mod(base, x, p);
+///         let log = primes::discrete_log(base, target, p).unwrap();
+///         assert!(log <= x && log < primes::multiplicative_order(base, p).unwrap());
+///         assert_eq!(primes::pow_mod(base, log, p), target);
+///     }
+/// }
+/// // 2 only generates half of the units mod 10^9 + 7, so it can't reach the generator 5.
+/// assert_eq!(primes::discrete_log(2, 5, 1_000_000_007), None);
+/// // Prime powers.
+/// assert_eq!(primes::discrete_log(3, primes::pow_mod(3, 12_345, 5u64.pow(12)), 5u64.pow(12)), Some(12_345));
+/// ```
+#[cfg(feature = "std")]
+pub fn discrete_log(base: u64, target: u64, modulus: u64) -> Option<u64> {
+    if modulus == 0 {
+        return None;
+    }
+    let (base, mut target, mut modulus) = (base % modulus, target % modulus, modulus);
+    // Divide the factors `base` shares with `modulus` out of the congruence, one power of `base` at a time:
+    // `base ^ x = target` becomes `scale * base ^ (x - k) = target` with `base` invertible.
+    let (mut scale, mut k) = (1 % modulus, 0);
+    loop {
+        if scale == target {
+            return Some(k);
+        }
+        let g = gcd(base, modulus);
+        if g == 1 {
+            break;
+        }
+        if target % g != 0 {
+            return None;
+        }
+        (target, modulus) = (target / g, modulus / g);
+        scale = mul_mod(scale, base / g, modulus);
+        k += 1;
+    }
+    // Baby steps: `target * base ^ j` for `j < step`, keeping the largest `j` for each value,
+    // so the first giant step `scale * base ^ (i * step)` to match gives the smallest `x = i * step - j`.
+    let step = crate::isqrt(modulus) + 1;
+    let mut baby = std::collections::HashMap::new();
+    let mut value = target;
+    for j in 0..step {
+        baby.insert(value, j);
+        value = mul_mod(value, base, modulus);
+    }
+    let giant = pow_mod(base, step, modulus);
+    let mut value = scale;
+    for i in 1..=step {
+        value = mul_mod(value, giant, modulus);
+        if let Some(&j) = baby.get(&value) {
+            return Some(k + i * step - j);
+        }
+    }
+    None
+}
+
+/// Returns the greatest common divisor of `a` and `b`.
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Bases for which Miller-Rabin is deterministic over all `u64`.
+pub(crate) const WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+/// Returns `true` if the odd number `n > 37` is prime, or `false` otherwise.
+pub(crate) fn miller_rabin(n: u64) -> bool {
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    'witness: for a in WITNESSES {
+        let mut x = pow_mod(a, d, n);
+        if x == 1 || x == n - 1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = mul_mod(x, x, n);
+            if x == n - 1 {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
@@ -0,0 +1,549 @@
+//! Segmented sieves computing multiplicative functions for every number below a limit.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::MulAssign;
+
+use crate::sieve::as_index;
+use crate::{divisors, is_prime, isqrt, up_to};
+
+/// How many numbers are factored at once, bounding the memory used by the sieves.
+const WINDOW: u64 = 32_768;
+
+/// Returns an iterator over Euler's totient `φ(k)` for every `k` from `1` to `n - 1`.
+///
+/// Memory usage is bounded, as numbers are sieved in fixed-size windows.
+///
+/// # Examples
+///
+/// ```
+/// let phi: Vec<_> = primes::phi_below(21).collect();
+/// assert_eq!(
+///     &phi,
+///     &[1, 1, 2, 2, 4, 2, 6, 4, 6, 4, 10, 4, 12, 6, 8, 8, 16, 6, 18, 8]
+/// );
+///
+/// let phi: Vec<_> = primes::phi_below(100_000).collect();
+/// for p in primes::below(99_999) {
+///     assert_eq!(phi[p as usize - 1], p - 1);
+/// }
+///
+/// assert_eq!(primes::phi_below(10_001).sum::<u64>(), 30_397_486);
+/// ```
+pub fn phi_below(n: u64) -> impl Iterator<Item = u64> {
+    Multiplicative::new(n, 1, |p, e| p.pow(e - 1) * (p - 1))
+}
+
+/// Returns an iterator over the Möbius function `μ(k)` for every `k` from `1` to `n - 1`.
+///
+/// `μ(k)` is `0` if `k` has a square factor, and otherwise `1` or `-1` as `k` has an even or odd number of prime factors.
+///
+/// Memory usage is bounded, as numbers are sieved in fixed-size windows.
+///
+/// # Examples
+///
+/// ```
+/// let mobius: Vec<_> = primes::mobius_below(31).collect();
+/// assert_eq!(
+///     &mobius,
+///     &[
+///         1, -1, -1, 0, -1, 1, -1, 0, 0, 1, -1, 0, -1, 1, 1, 0, -1, 0, -1, 0, 1, 1, -1, 0, 0, 1,
+///         0, 0, -1, -1
+///     ]
+/// );
+///
+/// let mertens: i64 = primes::mobius_below(10_001).map(i64::from).sum();
+/// assert_eq!(mertens, -23);
+///
+/// for (k, mobius) in (1..).zip(primes::mobius_below(10_000)) {
+///     let square_free = primes::divisors(k).all(|(_, exponent)| exponent == 1);
+///     assert_eq!(mobius == 0, !square_free);
+/// }
+/// ```
+pub fn mobius_below(n: u64) -> impl Iterator<Item = i8> {
+    Multiplicative::new(n, 1, |_, e| if e == 1 { -1 } else { 0 })
+}
+
+/// Returns an iterator over the number of divisors `τ(k)` for every `k` from `1` to `n - 1`.
+///
+/// Memory usage is bounded, as numbers are sieved in fixed-size windows.
+///
+/// # Examples
+///
+/// ```
+/// let tau: Vec<_> = primes::divisor_count_below(31).collect();
+/// assert_eq!(
+///     &tau,
+///     &[
+///         1, 2, 2, 3, 2, 4, 2, 4, 3, 4, 2, 6, 2, 4, 4, 5, 2, 6, 2, 6, 4, 4, 2, 8, 3, 4, 4, 6, 2, 8
+///     ]
+/// );
+///
+/// assert_eq!(primes::divisor_count_below(1_000).nth(839), Some(32));
+///
+/// let sum: u64 = primes::divisor_count_below(100_000).map(u64::from).sum();
+/// assert_eq!(sum, (1..100_000).map(|d| 99_999 / d).sum());
+/// ```
+pub fn divisor_count_below(n: u64) -> impl Iterator<Item = u32> {
+    Multiplicative::new(n, 1, |_, e| e + 1)
+}
+
+/// Returns an iterator over the sum of divisors `σ(k)` for every `k` from `1` to `n - 1`.
+///
+/// Memory usage is bounded, as numbers are sieved in fixed-size windows.
+///
+/// `σ(k) < 8k` for every `u64`, so the sums cannot overflow for `n` up to `2^61`.
+///
+/// # Examples
+///
+/// ```
+/// let sigma: Vec<_> = primes::divisor_sum_below(100_001).collect();
+/// assert_eq!(sigma[27], 56);
+///
+/// let abundant = (1..).zip(&sigma).filter(|&(k, &sigma)| sigma > 2 * k).count();
+/// assert_eq!(abundant, 24_795);
+///
+/// for k in (1..=100_000).step_by(97) {
+///     assert_eq!(Some(sigma[k as usize - 1]), primes::divisor_sum(k));
+/// }
+/// ```
+pub fn divisor_sum_below(n: u64) -> impl Iterator<Item = u64> {
+    Multiplicative::new(n, 1, prime_power_divisor_sum)
+}
+
+/// Returns the sum of the divisors of `n`, `σ(n)`, with `σ(0)` taken to be `0`,
+/// or `None` if it doesn't fit in a `u64`.
+///
+/// `σ(n) < 8n` for every `u64`, so the sum always fits for `n` below `2^61`,
+/// but above that it can overflow, as for `3 * 2^62`, whose divisors sum to `4 * (2^63 - 1)`.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(primes::divisor_sum(0), Some(0));
+/// assert_eq!(primes::divisor_sum(1), Some(1));
+/// assert_eq!(primes::divisor_sum(28), Some(56));
+///
+/// assert_eq!(primes::divisor_sum(1 << 60), Some((1 << 61) - 1));
+///
+/// let highly_abundant = 2u64.pow(7) * 3u64.pow(4) * 5 * 5 * 7 * 7 * 11 * 13 * 17 * 19 * 23 * 29 * 31 * 37;
+/// assert_eq!(primes::divisor_sum(highly_abundant), Some(2_886_954_599_079_936_000));
+///
+/// // The top of the range, where the sum only just fits, or doesn't.
+/// assert_eq!(primes::divisor_sum(1 << 63), Some(u64::MAX));
+/// assert_eq!(primes::divisor_sum(3 << 62), None);
+/// assert_eq!(primes::divisor_sum(3u64.pow(40)), Some(18_236_498_188_585_393_201));
+/// assert_eq!(primes::divisor_sum(u64::MAX), None);
+/// assert_eq!(primes::divisor_sum(1 << 61), Some((1 << 62) - 1));
+/// ```
+pub fn divisor_sum(n: u64) -> Option<u64> {
+    if n == 0 {
+        return Some(0);
+    }
+    divisors(n).try_fold(1u64, |sigma, (p, e)| {
+        sigma.checked_mul(checked_prime_power_divisor_sum(
+            p,
+            u32::try_from(e).unwrap(),
+        )?)
+    })
+}
+
+/// Returns every `n` with `φ(n) = m`, in increasing order.
+///
+/// `φ` is multiplicative, with `φ(p^k) = (p - 1) p^(k - 1)`, so each such `n` is a product of prime powers
+/// whose primes are one more than a divisor of `m`.
+/// These are searched from the largest prime down, dividing `m` by each prime power's totient along the way.
+/// Only the `n` that fit in a `u64` are returned.
+///
+/// `φ(n)` is even for every `n > 2`, so there are none for odd `m > 1`, or for `m = 0`,
+/// and some even `m`, such as 14, are not totients either.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(primes::inverse_phi(0), []);
+/// assert_eq!(primes::inverse_phi(1), [1, 2]);
+/// assert_eq!(primes::inverse_phi(4), [5, 8, 10, 12]);
+/// assert_eq!(primes::inverse_phi(14), []);
+/// assert_eq!(primes::inverse_phi(15), []);
+/// assert_eq!(primes::inverse_phi(24), [35, 39, 45, 52, 56, 70, 72, 78, 84, 90]);
+///
+/// // φ(n) >= sqrt(n / 2), so every n with φ(n) < 500 is below 500,000.
+/// let phi: Vec<_> = primes::phi_below(500_000).collect();
+/// let mut expected = vec![Vec::new(); 500];
+/// for (i, &m) in phi.iter().enumerate() {
+///     if let Some(list) = expected.get_mut(m as usize) {
+///         list.push(i as u64 + 1);
+///     }
+/// }
+/// for (m, list) in expected.iter().enumerate() {
+///     assert_eq!(&primes::inverse_phi(m as u64), list);
+/// }
+///
+/// let m = 1 << 40;
+/// for n in primes::inverse_phi(m) {
+///     let phi = primes::divisors(n).map(|(p, k)| (p - 1) * p.pow(k as u32 - 1)).product::<u64>();
+///     assert_eq!(phi, m);
+/// }
+/// ```
+pub fn inverse_phi(m: u64) -> Vec<u64> {
+    let mut found = Vec::new();
+    if m == 0 {
+        return found;
+    }
+    let mut primes: Vec<u64> = all_divisors(m)
+        .into_iter()
+        .filter_map(|d| d.checked_add(1))
+        .filter(|&p| is_prime(p))
+        .collect();
+    primes.reverse();
+    inverse_phi_from(m, 1, &primes, &mut found);
+    found.sort_unstable();
+    found
+}
+
+/// Pushes `n` times every product of powers of distinct primes from `primes` with totient `m`.
+fn inverse_phi_from(m: u64, n: u64, primes: &[u64], found: &mut Vec<u64>) {
+    if m == 1 {
+        found.push(n);
+    }
+    for (i, &p) in primes.iter().enumerate() {
+        if !m.is_multiple_of(p - 1) {
+            continue;
+        }
+        let (mut rest, mut product) = (m / (p - 1), n.checked_mul(p));
+        while let Some(n) = product {
+            inverse_phi_from(rest, n, &primes[i + 1..], found);
+            if !rest.is_multiple_of(p) {
+                break;
+            }
+            rest /= p;
+            product = n.checked_mul(p);
+        }
+    }
+}
+
+/// Returns every divisor of `n > 0`, in increasing order.
+fn all_divisors(n: u64) -> Vec<u64> {
+    let mut all = vec![1];
+    for (p, k) in divisors(n) {
+        let len = all.len();
+        let mut power = 1;
+        for _ in 0..k {
+            power *= p;
+            all.extend_from_within(..len);
+            let end = all.len();
+            for d in &mut all[end - len..] {
+                *d *= power;
+            }
+        }
+    }
+    all.sort_unstable();
+    all
+}
+
+/// Returns `true` if `n` is practical, which is when every number from 1 to `n` is a sum of distinct divisors of `n`,
+/// or `false` otherwise.
+///
+/// By Stewart and Sierpiński, that is when, with the prime factors in increasing order, the first is 2,
+/// and each later one is at most 1 more than `σ` of the part of `n` made of the primes before it.
+/// So the only odd practical number is 1, which is practical as it is its own divisor, and 0 isn't, having no divisors to sum.
+///
+/// # Examples
+///
+/// ```
+/// assert!(primes::is_practical(1));
+/// assert!(primes::is_practical(12));
+/// assert!(!primes::is_practical(10));
+/// assert!(!primes::is_practical(0));
+/// assert!(!primes::is_practical(3));
+/// for k in 0..64 {
+///     assert!(primes::is_practical(1 << k));
+/// }
+/// assert!(primes::is_practical(2u64.pow(61) * 3));
+/// assert!(!primes::is_practical(18_446_744_073_709_551_557));
+/// assert!(!primes::is_practical(2 * 1_000_003));
+///
+/// // Against the definition, from the sums of subsets of the divisors.
+/// for n in 1..600usize {
+///     let mut sums = vec![false; 2 * 600 * 8];
+///     sums[0] = true;
+///     for d in (1..=n).filter(|d| n % d == 0) {
+///         for s in (d..sums.len()).rev() {
+///             sums[s] |= sums[s - d];
+///         }
+///     }
+///     assert_eq!(primes::is_practical(n as u64), sums[1..=n].iter().all(|&s| s), "{n}");
+/// }
+/// ```
+pub fn is_practical(n: u64) -> bool {
+    if !n.is_multiple_of(2) {
+        return n == 1;
+    }
+    // Once σ saturates, every prime left is certainly small enough.
+    let mut sigma = 1u64;
+    for (p, e) in divisors(n) {
+        if p > sigma.saturating_add(1) {
+            return false;
+        }
+        sigma = sigma.saturating_mul(
+            checked_prime_power_divisor_sum(p, u32::try_from(e).unwrap()).unwrap_or(u64::MAX),
+        );
+    }
+    n > 0
+}
+
+/// Returns an iterator over the practical numbers less than `n`, as [`is_practical`] decides them.
+///
+/// Rather than factoring each number, the numbers are factored a window at a time by the same sieve as [`factorizations_below`],
+/// with the running `σ` of each checked against its primes as they are found in increasing order.
+///
+/// # Examples
+///
+/// ```
+/// let practical: Vec<_> = primes::practical_numbers_below(100).collect();
+/// assert_eq!(
+///     practical,
+///     [
+///         1, 2, 4, 6, 8, 12, 16, 18, 20, 24, 28, 30, 32, 36, 40, 42, 48, 54, 56, 60, 64, 66, 72,
+///         78, 80, 84, 88, 90, 96
+///     ]
+/// );
+/// assert_eq!(primes::practical_numbers_below(1).next(), None);
+/// assert_eq!(primes::practical_numbers_below(10_000).count(), 1_455);
+///
+/// let practical: Vec<_> = primes::practical_numbers_below(100_000).collect();
+/// assert!(practical.iter().all(|&k| k == 1 || k % 2 == 0));
+/// assert!(practical.iter().copied().eq((1..100_000).filter(|&k| primes::is_practical(k))));
+/// assert_eq!(practical.len(), 11_750);
+/// ```
+pub fn practical_numbers_below(n: u64) -> impl Iterator<Item = u64> {
+    Practical {
+        sieve: FactorSieve::new(1, n),
+        sigma: Vec::new(),
+        k: 1,
+        i: 0,
+    }
+}
+
+/// Returns `σ(p^e) = 1 + p + ... + p^e` without overflowing on the way, for `p^e` up to `2^61`.
+fn prime_power_divisor_sum(p: u64, e: u32) -> u64 {
+    (0..e).fold(1, |sum, _| sum * p + 1)
+}
+
+/// Returns `σ(p^e)`, or `None` if it doesn't fit in a `u64`.
+fn checked_prime_power_divisor_sum(p: u64, e: u32) -> Option<u64> {
+    (0..e).try_fold(1u64, |sum, _| sum.checked_mul(p)?.checked_add(1))
+}
+
+/// Returns an iterator over every `k` from `2` to `n - 1` together with its prime factorization.
+///
+/// The factorizations are in the same form as [`divisors`](crate::divisors), but are found by sieving rather than trial division.
+/// Memory usage is bounded, as numbers are sieved in fixed-size windows.
+///
+/// # Examples
+///
+/// ```
+/// let factorizations: Vec<_> = primes::factorizations_below(7).collect();
+/// assert_eq!(
+///     &factorizations,
+///     &[
+///         (2, vec![(2, 1)]),
+///         (3, vec![(3, 1)]),
+///         (4, vec![(2, 2)]),
+///         (5, vec![(5, 1)]),
+///         (6, vec![(2, 1), (3, 1)]),
+///     ]
+/// );
+///
+/// for (k, factors) in primes::factorizations_below(10_000) {
+///     assert!(factors.into_iter().eq(primes::divisors(k)));
+/// }
+///
+/// let total: u64 = primes::factorizations_below(100_000)
+///     .flat_map(|(_, factors)| factors)
+///     .map(|(_, exponent)| exponent)
+///     .sum();
+/// let mut expected = 0;
+/// for p in primes::below(100_000) {
+///     let mut power = p;
+///     while power < 100_000 {
+///         expected += 99_999 / power;
+///         power *= p;
+///     }
+/// }
+/// assert_eq!(total, expected);
+/// ```
+pub fn factorizations_below(n: u64) -> impl Iterator<Item = (u64, Vec<(u64, u64)>)> {
+    Factorizations {
+        sieve: FactorSieve::new(2, n),
+        factors: Vec::new(),
+        k: 2,
+        i: 0,
+    }
+}
+
+/// Factors every number in a range, a window at a time.
+pub(crate) struct FactorSieve {
+    primes: Vec<u64>,
+    remaining: Vec<u64>,
+    start: u64,
+    end: u64,
+}
+
+impl FactorSieve {
+    /// Factors the numbers from `start` to `end - 1`, where `start` is at least `1`.
+    pub(crate) fn new(start: u64, end: u64) -> Self {
+        Self {
+            primes: up_to(isqrt(end.saturating_sub(1))).collect(),
+            remaining: Vec::new(),
+            start,
+            end,
+        }
+    }
+
+    /// Returns the number of values in the next window, or `0` once the range is exhausted.
+    pub(crate) fn window_len(&self) -> usize {
+        self.end.saturating_sub(self.start).min(WINDOW) as usize
+    }
+
+    /// Factors the next window, calling `f(i, p, e)` for each `p^e` exactly dividing the `i`th number in the window.
+    ///
+    /// For any one number, its primes are visited in increasing order.
+    pub(crate) fn next_window(&mut self, mut f: impl FnMut(usize, u64, u32)) {
+        let start = self.start;
+        let end = start + self.window_len() as u64;
+        self.remaining.clear();
+        self.remaining.extend(start..end);
+        for &p in &self.primes {
+            if p * p >= end {
+                break;
+            }
+            for m in (start.div_ceil(p) * p..end).step_by(as_index(p)) {
+                let i = as_index(m - start);
+                let mut exponent = 0;
+                while self.remaining[i].is_multiple_of(p) {
+                    self.remaining[i] /= p;
+                    exponent += 1;
+                }
+                f(i, p, exponent);
+            }
+        }
+        for (i, &remaining) in self.remaining.iter().enumerate() {
+            if remaining > 1 {
+                f(i, remaining, 1);
+            }
+        }
+        self.start = end;
+    }
+}
+
+/// An iterator over a multiplicative function, defined by its values at prime powers.
+struct Multiplicative<T> {
+    sieve: FactorSieve,
+    one: T,
+    prime_power: fn(u64, u32) -> T,
+    values: Vec<T>,
+    i: usize,
+}
+
+impl<T> Multiplicative<T> {
+    fn new(n: u64, one: T, prime_power: fn(u64, u32) -> T) -> Self {
+        Self {
+            sieve: FactorSieve::new(1, n),
+            one,
+            prime_power,
+            values: Vec::new(),
+            i: 0,
+        }
+    }
+}
+
+impl<T: Copy + MulAssign> Iterator for Multiplicative<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.i == self.values.len() {
+            let len = self.sieve.window_len();
+            if len == 0 {
+                return None;
+            }
+            self.values.clear();
+            self.values.resize(len, self.one);
+            let (values, prime_power) = (&mut self.values, self.prime_power);
+            self.sieve
+                .next_window(|i, p, e| values[i] *= prime_power(p, e));
+            self.i = 0;
+        }
+        self.i += 1;
+        Some(self.values[self.i - 1])
+    }
+}
+
+/// An iterator over the practical numbers.
+struct Practical {
+    sieve: FactorSieve,
+    /// `σ` of the primes found so far for each number in the window, or `None` once one is too large for it to be practical.
+    sigma: Vec<Option<u64>>,
+    k: u64,
+    i: usize,
+}
+
+impl Iterator for Practical {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.i == self.sigma.len() {
+                let len = self.sieve.window_len();
+                if len == 0 {
+                    return None;
+                }
+                self.sigma.clear();
+                self.sigma.resize(len, Some(1));
+                let sigma = &mut self.sigma;
+                self.sieve.next_window(|i, p, e| {
+                    sigma[i] = sigma[i]
+                        .filter(|&sigma| p <= sigma + 1)
+                        .map(|sigma| sigma * prime_power_divisor_sum(p, e));
+                });
+                self.i = 0;
+            }
+            self.i += 1;
+            self.k += 1;
+            if self.sigma[self.i - 1].is_some() {
+                return Some(self.k - 1);
+            }
+        }
+    }
+}
+
+/// An iterator over numbers and their prime factorizations.
+struct Factorizations {
+    sieve: FactorSieve,
+    factors: Vec<Vec<(u64, u64)>>,
+    k: u64,
+    i: usize,
+}
+
+impl Iterator for Factorizations {
+    type Item = (u64, Vec<(u64, u64)>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.i == self.factors.len() {
+            let len = self.sieve.window_len();
+            if len == 0 {
+                return None;
+            }
+            self.factors.clear();
+            self.factors.resize(len, Vec::new());
+            let factors = &mut self.factors;
+            self.sieve
+                .next_window(|i, p, e| factors[i].push((p, e as u64)));
+            self.i = 0;
+        }
+        self.i += 1;
+        self.k += 1;
+        Some((self.k - 1, core::mem::take(&mut self.factors[self.i - 1])))
+    }
+}
@@ -0,0 +1,60 @@
+//! The prime nearest to a number.
+
+use crate::is_prime;
+
+/// Returns the prime closest to `n`, which is `n` itself if it is prime,
+/// or the smaller of the two if `n` is exactly midway between a prime below it and a prime above it.
+///
+/// The numbers either side of `n` are tested in turn with [`is_prime`], moving outward one step at a time,
+/// the one below first so that it wins a tie, so the time taken depends on the gap around `n` rather than its size.
+/// Past the largest prime below `2^64`, only the numbers below `n` are tested, and 2 is the nearest prime to 0 and 1.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(primes::nearest_prime(10), 11);
+/// assert_eq!(primes::nearest_prime(13), 13);
+/// // 9 is as far from 7 as from 11, and 4 from 3 as from 5, so the smaller one is taken.
+/// assert_eq!(primes::nearest_prime(9), 7);
+/// assert_eq!(primes::nearest_prime(4), 3);
+/// assert_eq!(primes::nearest_prime(0), 2);
+/// assert_eq!(primes::nearest_prime(1), 2);
+/// assert_eq!(primes::nearest_prime(2), 2);
+///
+/// // 2^61 - 1 is a Mersenne prime, with the next prime 2^61 + 15.
+/// let n = 1 << 61;
+/// let prev = (2..=n).rev().find(|&m| primes::is_prime(m)).unwrap();
+/// let next = (n..).find(|&m| primes::is_prime(m)).unwrap();
+/// assert_eq!((prev, next), (n - 1, n + 15));
+/// assert_eq!(primes::nearest_prime(n), n - 1);
+/// assert_eq!(primes::nearest_prime(n + 7), n - 1);
+/// assert_eq!(primes::nearest_prime(n + 8), n + 15);
+///
+/// // Above the largest prime that fits, the search only goes down, without overflowing.
+/// let largest = 18_446_744_073_709_551_557;
+/// assert_eq!(primes::nearest_prime(u64::MAX), largest);
+/// assert_eq!(primes::nearest_prime(largest + 1), largest);
+///
+/// // Against the primes either side of each number.
+/// let all: Vec<_> = primes::below(100_100).collect();
+/// for n in 2..100_000 {
+///     let i = all.partition_point(|&p| p < n);
+///     let (below, above) = (all[i.saturating_sub(1)], all[i]);
+///     let nearest = if all[i] == n || above - n < n - below { above } else { below };
+///     assert_eq!(primes::nearest_prime(n), nearest);
+/// }
+/// ```
+pub fn nearest_prime(n: u64) -> u64 {
+    if n <= 2 {
+        return 2;
+    }
+    (0..)
+        .find_map(|d| {
+            let below = n - d;
+            if is_prime(below) {
+                return Some(below);
+            }
+            n.checked_add(d).filter(|&above| is_prime(above))
+        })
+        .unwrap()
+}
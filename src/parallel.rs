@@ -0,0 +1,148 @@
+//! Sieving segments and factorizing values on rayon's thread pool.
+
+use std::sync::Arc;
+use std::vec::Vec;
+
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+
+use crate::sieve::{base_primes, Sieve, Wheel, W30, WHEEL_PRIMES, WORDS};
+use crate::{isqrt, small_primes, Divisors};
+
+/// The number of runs of segments each thread gets on average,
+/// so that a thread that finishes early can take over another's work.
+const RUNS_PER_THREAD: u64 = 4;
+
+/// Returns a `Vec` of the primes less than or equal to `n`, sieving on rayon's global thread pool.
+///
+/// The base primes up to `sqrt(n)` are found first, and the segments are then split into runs of consecutive segments,
+/// each sieved as a task that finds where each base prime starts once and reuses a single segment buffer.
+/// The runs are concatenated in order, so the result is the same as [`below_vec`](crate::below_vec),
+/// which is used directly when the pool has only one thread.
+/// How much faster it is depends on the cores available, and `examples/parallel_scaling.rs` times it for several thread counts.
+///
+/// Only available with the `rayon` feature.
+///
+/// # Examples
+///
+/// ```
+/// for n in [0, 1, 2, 30, 240_000, 1_000_003, 4_000_037] {
+///     assert_eq!(primes::below_parallel(n), primes::below_vec(n));
+/// }
+/// ```
+pub fn below_parallel(n: u64) -> Vec<u64> {
+    if rayon::current_num_threads() == 1 {
+        return crate::below_vec(n);
+    }
+    sieve_runs(n)
+}
+
+/// Like [`below_parallel`], but on a new pool of `threads` threads rather than the global pool.
+///
+/// # Panics
+///
+/// Panics if `threads` is 0, or if the pool can't be created.
+///
+/// # Examples
+///
+/// ```
+/// let expected = primes::below_vec(2_000_003);
+/// for threads in 1..=5 {
+///     assert_eq!(primes::below_parallel_on(2_000_003, threads), expected);
+/// }
+/// ```
+pub fn below_parallel_on(n: u64, threads: usize) -> Vec<u64> {
+    assert!(threads > 0, "at least one thread is needed");
+    if threads == 1 {
+        return crate::below_vec(n);
+    }
+    ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .expect("the thread pool could not be created")
+        .install(|| sieve_runs(n))
+}
+
+/// Sieves the primes up to `n` in runs of segments on the current pool.
+fn sieve_runs(n: u64) -> Vec<u64> {
+    let mut primes: Vec<u64> = WHEEL_PRIMES.into_iter().take_while(|&p| p <= n).collect();
+    if n < 7 {
+        return primes;
+    }
+    let base = base_primes(n);
+    // The slots before this one hold every value up to n. For n = u64::MAX that is the slot after the last,
+    // as u64::MAX isn't on the wheel.
+    let segments = W30::slot(n.saturating_add(1)).div_ceil(WORDS as u64 * 64);
+    let tasks = rayon::current_num_threads() as u64 * RUNS_PER_THREAD;
+    let per_run = segments.div_ceil(tasks);
+    let runs: Vec<Vec<u64>> = (0..segments.div_ceil(per_run))
+        .into_par_iter()
+        .map(|run| {
+            let first = run * per_run;
+            let last = (first + per_run).min(segments);
+            // Each run carries its own offsets through its segments.
+            let mut base = base.clone();
+            let mut sieve = Sieve::at(W30, WORDS, first);
+            let mut run = Vec::new();
+            for index in first..last {
+                if index > first {
+                    sieve.slide();
+                }
+                sieve.sieve_with(&mut base);
+                run.extend(sieve.primes().take_while(|&p| p <= n));
+            }
+            run
+        })
+        .collect();
+    primes.reserve_exact(runs.iter().map(Vec::len).sum());
+    for run in runs {
+        primes.extend(run);
+    }
+    primes
+}
+
+/// Returns the prime factorization of each value, as given by [`divisors`](crate::divisors),
+/// factorizing on rayon's global thread pool.
+///
+/// The primes up to the square root of the largest value are found once,
+/// and shared by every task rather than each factorization looking them up.
+/// The factorizations are in the same order as the values.
+///
+/// Only available with the `rayon` feature.
+///
+/// # Examples
+///
+/// ```
+/// let factorizations = primes::divisors_many_parallel(&[504, 0, 1, 2, 2, 53]);
+/// assert_eq!(
+///     factorizations,
+///     [vec![(2, 3), (3, 2), (7, 1)], vec![], vec![], vec![(2, 1)], vec![(2, 1)], vec![(53, 1)]],
+/// );
+///
+/// let mut n = 1u64;
+/// let values: Vec<_> = (0..200)
+///     .map(|_| {
+///         n = n.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+///         n >> 24
+///     })
+///     .collect();
+/// let factorizations = primes::divisors_many_parallel(&values);
+/// for (&value, factorization) in values.iter().zip(factorizations) {
+///     assert_eq!(factorization, primes::divisors(value).collect::<Vec<_>>());
+/// }
+/// ```
+pub fn divisors_many_parallel(values: &[u64]) -> Vec<Vec<(u64, u64)>> {
+    let Some(&max) = values.iter().max() else {
+        return Vec::new();
+    };
+    let root = isqrt(max);
+    let primes = small_primes::up_to(root).unwrap_or_else(|| {
+        crate::up_to(root)
+            .map(|p| u32::try_from(p).unwrap())
+            .collect()
+    });
+    values
+        .par_iter()
+        .map(|&n| Divisors::with_table(n, Arc::clone(&primes)).collect())
+        .collect()
+}
@@ -0,0 +1,114 @@
+//! Powers of primes, which are the numbers with a single prime factor.
+
+use alloc::collections::BinaryHeap;
+
+use core::cmp::Reverse;
+use core::iter;
+
+use crate::roots::square_at_most;
+use crate::{below, is_prime, kth_root};
+
+/// Returns an iterator over the prime powers `p^k` less than or equal to `n`, with `k` at least 1, in increasing order.
+///
+/// The primes come from [`below`], and each prime up to `sqrt(n)` is put on a small heap as it goes past,
+/// along with its square, which is yielded once the primes catch up with it, and replaced with the next power.
+/// The heap holds one power for each prime up to `sqrt(n)`, at most, and the higher powers are never sorted as a whole.
+///
+/// # Examples
+///
+/// ```
+/// let powers: Vec<_> = primes::prime_powers_below(100).collect();
+/// assert_eq!(
+///     powers,
+///     [
+///         2, 3, 4, 5, 7, 8, 9, 11, 13, 16, 17, 19, 23, 25, 27, 29, 31, 32, 37, 41, 43, 47,
+///         49, 53, 59, 61, 64, 67, 71, 73, 79, 81, 83, 89, 97,
+///     ]
+/// );
+/// assert_eq!(primes::prime_powers_below(1).next(), None);
+/// assert_eq!(primes::prime_powers_below(4).collect::<Vec<_>>(), [2, 3, 4]);
+///
+/// // The primes, then the squares, cubes and higher powers of the primes small enough to have them.
+/// let n = 1_000_000;
+/// let pi = |n| primes::below(n).count();
+/// let higher: usize = (2..64).map(|k| pi(primes::kth_root(n, k))).sum();
+/// assert_eq!(primes::prime_powers_below(n).count(), pi(n) + higher);
+///
+/// let powers: Vec<_> = primes::prime_powers_below(n).collect();
+/// assert!(powers.windows(2).all(|pair| pair[0] < pair[1]));
+/// for m in 0..100_000 {
+///     let single = primes::divisors(m).count() == 1;
+///     assert_eq!(powers.binary_search(&m).is_ok(), single);
+/// }
+///
+/// // Without a limit to speak of, the primes still only go on the heap as they are passed.
+/// assert!(primes::prime_powers_below(u64::MAX).take(10_000).eq(powers[..10_000].iter().copied()));
+/// ```
+pub fn prime_powers_below(n: u64) -> impl Iterator<Item = u64> {
+    let mut primes = below(n);
+    // The next power of each prime up to the square root still to be yielded, with the prime that multiplies it.
+    let mut powers = BinaryHeap::new();
+    iter::from_fn(move || {
+        let power = powers.peek().map(|&Reverse((power, _))| power);
+        match primes.peek() {
+            Some(p) if power.is_none_or(|power| p < power) => {
+                primes.next();
+                if square_at_most(p, n) {
+                    powers.push(Reverse((p * p, p)));
+                }
+                Some(p)
+            }
+            _ => {
+                let Reverse((power, p)) = powers.pop()?;
+                if let Some(next) = power.checked_mul(p).filter(|&next| next <= n) {
+                    powers.push(Reverse((next, p)));
+                }
+                Some(power)
+            }
+        }
+    })
+}
+
+/// Returns the smallest prime power `p^k` greater than or equal to `n`, with `k` at least 1,
+/// or `None` if there is none up to `u64::MAX`, which is the case past its largest prime.
+///
+/// For each exponent `k`, the smallest prime with a `k`th power at least `n` is found by testing the numbers
+/// from `n^(1/k)` up with [`is_prime`], and the smallest of the powers is returned.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(primes::next_prime_power(121), Some(121));
+/// assert_eq!(primes::next_prime_power(122), Some(125));
+/// assert_eq!(primes::next_prime_power(0), Some(2));
+/// assert_eq!(primes::next_prime_power(24), Some(25));
+/// assert_eq!(primes::next_prime_power(26), Some(27));
+///
+/// // Between the primes either side of 2^63, with the largest power of 2 that fits.
+/// assert_eq!(primes::next_prime_power((1 << 63) - 24), Some(1 << 63));
+/// assert_eq!(primes::next_prime_power((1 << 63) + 1), Some((1 << 63) + 29));
+/// assert_eq!(primes::next_prime_power(18_446_744_073_709_551_557), Some(18_446_744_073_709_551_557));
+/// assert_eq!(primes::next_prime_power(18_446_744_073_709_551_558), None);
+/// assert_eq!(primes::next_prime_power(u64::MAX), None);
+///
+/// let powers: Vec<_> = primes::prime_powers_below(100_000).collect();
+/// for n in 0..=99_991 {
+///     let next = powers[powers.partition_point(|&power| power < n)];
+///     assert_eq!(primes::next_prime_power(n), Some(next));
+/// }
+/// ```
+pub fn next_prime_power(n: u64) -> Option<u64> {
+    (1..u64::BITS)
+        .filter_map(|k| {
+            // The smallest number whose `k`th power is at least `n`.
+            let root = kth_root(n, k);
+            let root = if root.pow(k) == n { root } else { root + 1 };
+            next_prime(root)?.checked_pow(k)
+        })
+        .min()
+}
+
+/// Returns the smallest prime at least `n`, or `None` if there is none up to `u64::MAX`.
+fn next_prime(n: u64) -> Option<u64> {
+    (n..=u64::MAX).find(|&m| is_prime(m))
+}
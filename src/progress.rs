@@ -0,0 +1,96 @@
+//! Reporting how far a long run of the sieve has got.
+
+use core::fmt;
+use core::iter::FusedIterator;
+
+use crate::Primes;
+
+/// A snapshot of how far a [`WithProgress`] iterator has got, passed to its callback by value.
+///
+/// [`for_each_segment`](crate::for_each_segment) already calls back once per segment,
+/// so it can report the same by looking at the last prime in each slice.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Progress {
+    /// Every value up to `position` has been sieved, and every prime up to it yielded:
+    /// it is the end of the last segment finished, or the limit, once the iterator reaches it.
+    pub position: u64,
+    /// The largest value that can be yielded, as [`Primes::limit`] returns.
+    pub limit: u64,
+    /// The number of primes yielded so far, as [`Primes::yielded`] returns.
+    pub yielded: u64,
+    /// The number of segments finished since the callback was attached.
+    pub segments: u64,
+}
+
+/// An iterator that calls back with its [`Progress`] each time it finishes a sieve segment,
+/// created by [`Primes::with_progress`] or [`PrimesCount::with_progress`](crate::PrimesCount::with_progress).
+///
+/// It yields the same primes as the iterator it was created from.
+pub struct WithProgress<F> {
+    primes: Primes,
+    callback: F,
+    /// The index of the segment being sieved, and its last value.
+    segment: (u64, u64),
+    segments: u64,
+    /// Whether the final callback has been made.
+    finished: bool,
+}
+
+impl<F: FnMut(Progress)> WithProgress<F> {
+    pub(crate) fn new(primes: Primes, callback: F) -> Self {
+        Self {
+            segment: primes.segment(),
+            primes,
+            callback,
+            segments: 0,
+            finished: false,
+        }
+    }
+
+    fn report(&mut self, position: u64, yielded: u64) {
+        (self.callback)(Progress {
+            position: position.min(self.primes.limit()),
+            limit: self.primes.limit(),
+            yielded,
+            segments: self.segments,
+        });
+    }
+}
+
+impl<F: FnMut(Progress)> Iterator for WithProgress<F> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.primes.next();
+        let (index, end) = self.primes.segment();
+        if index != self.segment.0 {
+            // Every prime in the segments passed over has been yielded, but the one just yielded is past them.
+            self.segments += index - self.segment.0;
+            let position = self.segment.1;
+            self.segment = (index, end);
+            self.report(position, self.primes.yielded() - u64::from(next.is_some()));
+        }
+        if next.is_none() && !self.finished {
+            self.finished = true;
+            self.segments += 1;
+            self.report(end, self.primes.yielded());
+        }
+        next
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.primes.size_hint()
+    }
+}
+
+impl<F: FnMut(Progress)> FusedIterator for WithProgress<F> {}
+
+impl<F> fmt::Debug for WithProgress<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WithProgress")
+            .field("primes", &self.primes)
+            .field("segments", &self.segments)
+            .finish_non_exhaustive()
+    }
+}
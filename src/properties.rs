@@ -0,0 +1,123 @@
+//! Predicates and summaries describing the prime factorization of a number.
+
+use alloc::vec::Vec;
+
+use crate::{divisors, first, is_prime, isqrt, up_to};
+
+/// Returns `true` if `n` is the product of exactly two primes, or `false` otherwise.
+///
+/// Squares of primes count as semiprimes.
+///
+/// Only trial divides up to the cube root of `n`, after which a single primality test decides.
+///
+/// # Examples
+///
+/// ```
+/// for n in [4, 6, 9, 10, 15] {
+///     assert!(primes::is_semiprime(n));
+/// }
+/// for n in [0, 1, 2, 8, 12, 30] {
+///     assert!(!primes::is_semiprime(n));
+/// }
+///
+/// assert!(primes::is_semiprime(999_999_937 * 999_999_929));
+/// assert!(!primes::is_semiprime(1_000_003 * 1_000_003 * 1_000_003));
+///
+/// for n in 0..10_000 {
+///     let factors: u64 = primes::divisors(n).map(|(_, exponent)| exponent).sum();
+///     assert_eq!(primes::is_semiprime(n), factors == 2);
+/// }
+/// ```
+pub fn is_semiprime(n: u64) -> bool {
+    if n < 4 {
+        return false;
+    }
+    for p in up_to(isqrt(n)) {
+        if p > n / p / p {
+            break;
+        }
+        if n.is_multiple_of(p) {
+            return is_prime(n / p);
+        }
+    }
+    // Every prime factor is above the cube root, so there are at most two of them.
+    !is_prime(n)
+}
+
+/// Returns the prime signature of `n`: the exponents in its prime factorization, largest first.
+///
+/// Two numbers have the same signature exactly when their factorizations have the same shape,
+/// so they share the number of divisors, and everything else that doesn't depend on which primes are involved.
+/// 1 has the empty signature, and so does 0, which [`divisors`] treats as 1.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(primes::prime_signature(360), [3, 2, 1]);
+/// assert_eq!(primes::prime_signature(1), []);
+/// assert_eq!(primes::prime_signature(0), []);
+/// assert_eq!(primes::prime_signature(1 << 63), [63]);
+/// for p in [2u64, 3, 97, 65_537] {
+///     for k in 1..=3 {
+///         assert_eq!(primes::prime_signature(p.pow(k)), [k]);
+///     }
+/// }
+///
+/// // The signature decides the number of divisors.
+/// for n in 1..3_000u64 {
+///     let count = (1..=n).filter(|d| n % d == 0).count() as u32;
+///     assert_eq!(primes::prime_signature(n).iter().map(|k| k + 1).product::<u32>(), count);
+/// }
+/// ```
+pub fn prime_signature(n: u64) -> Vec<u32> {
+    // No exponent can be above 63.
+    let mut signature: Vec<_> = divisors(n)
+        .map(|(_, exponent)| u32::try_from(exponent).unwrap())
+        .collect();
+    signature.sort_unstable_by(|a, b| b.cmp(a));
+    signature
+}
+
+/// Returns the smallest number with the given prime signature,
+/// or `None` if it is larger than `u64::MAX`.
+///
+/// The largest exponent goes to 2, the next to 3, and so on,
+/// so the exponents can be given in any order, and any zeros are left out, as they don't change the number.
+/// The empty signature is that of 1.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(primes::smallest_with_signature(&[1, 1, 1]), Some(30));
+/// assert_eq!(primes::smallest_with_signature(&[3, 2, 1]), Some(360));
+/// assert_eq!(primes::smallest_with_signature(&[1, 3, 0, 2]), Some(360));
+/// assert_eq!(primes::smallest_with_signature(&[]), Some(1));
+/// assert_eq!(primes::smallest_with_signature(&[63]), Some(1 << 63));
+///
+/// assert_eq!(primes::smallest_with_signature(&[64]), None);
+/// assert_eq!(primes::smallest_with_signature(&[40, 20]), None);
+/// assert_eq!(primes::smallest_with_signature(&[1; 15]), Some(614_889_782_588_491_410));
+/// assert_eq!(primes::smallest_with_signature(&[1; 16]), None);
+/// assert_eq!(primes::smallest_with_signature(&[1; 1_000_000]), None);
+/// assert_eq!(primes::smallest_with_signature(&[u32::MAX]), None);
+///
+/// // The smallest number with a signature has that signature, and no smaller number does.
+/// let mut seen = std::collections::HashSet::new();
+/// for n in 1..100_000 {
+///     let signature = primes::prime_signature(n);
+///     let smallest = primes::smallest_with_signature(&signature).unwrap();
+///     assert_eq!(primes::prime_signature(smallest), signature);
+///     assert!(smallest <= n);
+///     assert_eq!(smallest == n, seen.insert(signature));
+/// }
+/// ```
+pub fn smallest_with_signature(signature: &[u32]) -> Option<u64> {
+    let mut exponents: Vec<_> = signature.iter().copied().filter(|&k| k > 0).collect();
+    exponents.sort_unstable_by(|a, b| b.cmp(a));
+    // The primes are only sieved as far as the product stays in range, which is at most the first 15.
+    let mut product = 1u64;
+    for (p, k) in first(exponents.len() as u64).zip(exponents) {
+        product = product.checked_mul(p.checked_pow(k)?)?;
+    }
+    Some(product)
+}
@@ -0,0 +1,97 @@
+//! Primes whose reciprocals have the longest possible period in a given base.
+
+use crate::modular::{multiplicative_order, pow_mod};
+use crate::{divisors, up_to, Builder};
+
+/// Returns the period of the repeating digits of `1 / p` in `base`, which is the multiplicative order of `base` mod `p`,
+/// or `None` if the expansion terminates because `p` shares a factor with `base`.
+///
+/// A terminating expansion can be thought of as repeating zeros, with period 1, but they are told apart here.
+///
+/// # Examples
+///
+/// ```
+/// // 1 / 7 = 0.142857 142857 ...
+/// assert_eq!(primes::reciprocal_period(7, 10), Some(6));
+/// // 1 / 3 = 0.333 ...
+/// assert_eq!(primes::reciprocal_period(3, 10), Some(1));
+/// // 1 / 41 = 0.02439 02439 ...
+/// assert_eq!(primes::reciprocal_period(41, 10), Some(5));
+/// // 1 / 5 = 0.2
+/// assert_eq!(primes::reciprocal_period(5, 10), None);
+/// // 1 / 3 = 0.010101 ... in binary.
+/// assert_eq!(primes::reciprocal_period(3, 2), Some(2));
+///
+/// // Against long division, whose remainders cycle with the same period.
+/// for p in primes::between(3, 2_000).filter(|&p| p != 5) {
+///     let mut remainder = 1;
+///     let mut period = 0;
+///     loop {
+///         remainder = remainder * 10 % p;
+///         period += 1;
+///         if remainder == 1 {
+///             break;
+///         }
+///     }
+///     assert_eq!(primes::reciprocal_period(p, 10), Some(period));
+/// }
+/// ```
+pub fn reciprocal_period(p: u64, base: u64) -> Option<u64> {
+    multiplicative_order(base, p)
+}
+
+/// Returns an iterator over the full reptend primes in `base`: the primes `p` not dividing `base`,
+/// for which `1 / p` has the longest possible period, `p - 1`, in `base`.
+///
+/// These are the primes that `base` is a primitive root of.
+/// For each prime, `p - 1` is factorized using the small primes shared by [`divisors`],
+/// and `base ^ ((p - 1) / q) mod p` checked to not be 1 for each prime factor `q`.
+///
+/// If `base` is a perfect square, every power of it is a square too, so the only one there can be is 2, mod which 1 is a primitive root.
+/// For those bases, the iterator keeps searching without yielding anything else.
+///
+/// # Examples
+///
+/// ```
+/// let decimal: Vec<_> = primes::full_reptend_primes(10).take_while(|&p| p < 100).collect();
+/// assert_eq!(decimal, [7, 17, 19, 23, 29, 47, 59, 61, 97]);
+///
+/// let binary: Vec<_> = primes::full_reptend_primes(2).take(10).collect();
+/// assert_eq!(binary, [3, 5, 11, 13, 19, 29, 37, 53, 59, 61]);
+///
+/// for base in [2, 3, 10, 12] {
+///     assert!(primes::full_reptend_primes(base)
+///         .take(200)
+///         .all(|p| primes::reciprocal_period(p, base) == Some(p - 1)));
+/// }
+/// ```
+pub fn full_reptend_primes(base: u64) -> impl Iterator<Item = u64> {
+    Builder::new()
+        .build()
+        .filter(move |&p| is_primitive_root(base, p))
+}
+
+/// Returns an iterator over the full reptend primes in `base` less than or equal to `n`, like [`full_reptend_primes`].
+///
+/// # Examples
+///
+/// ```
+/// // OEIS A001913.
+/// assert_eq!(primes::full_reptend_primes_below(10, 10_000).count(), 467);
+/// assert_eq!(primes::full_reptend_primes_below(10, 97).last(), Some(97));
+///
+/// assert_eq!(primes::full_reptend_primes_below(4, 100_000).next(), None);
+/// assert_eq!(primes::full_reptend_primes_below(0, 100).next(), None);
+/// // Odd numbers are 1 mod 2, and 1 is the only unit mod 2.
+/// assert_eq!(primes::full_reptend_primes_below(9, 100_000).collect::<Vec<_>>(), [2]);
+/// assert_eq!(primes::full_reptend_primes_below(1, 100).collect::<Vec<_>>(), [2]);
+/// assert_eq!(primes::full_reptend_primes_below(3, 2).collect::<Vec<_>>(), [2]);
+/// ```
+pub fn full_reptend_primes_below(base: u64, n: u64) -> impl Iterator<Item = u64> {
+    up_to(n).filter(move |&p| is_primitive_root(base, p))
+}
+
+/// Returns `true` if `base` generates the units mod the prime `p`.
+fn is_primitive_root(base: u64, p: u64) -> bool {
+    !base.is_multiple_of(p) && divisors(p - 1).all(|(q, _)| pow_mod(base, (p - 1) / q, p) != 1)
+}
@@ -0,0 +1,172 @@
+//! Exact integer roots.
+
+/// Returns `floor(sqrt(n))`, exactly for every `u64`.
+///
+/// Newton's method is run in integers from a power of two above the root, so no floating point rounding is involved.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(primes::isqrt(0), 0);
+/// assert_eq!(primes::isqrt(24), 4);
+/// assert_eq!(primes::isqrt(25), 5);
+/// assert_eq!(primes::isqrt(u64::MAX), u32::MAX as u64);
+///
+/// for n in 0..1 << 20 {
+///     let root = primes::isqrt(n);
+///     assert!(root * root <= n && (root + 1) * (root + 1) > n);
+/// }
+///
+/// // Either side of every square, for roots spread up to the largest.
+/// for k in (2..u32::MAX as u64).step_by(65_521).chain([u32::MAX as u64]) {
+///     assert_eq!(primes::isqrt(k * k - 1), k - 1);
+///     assert_eq!(primes::isqrt(k * k), k);
+///     assert_eq!(primes::isqrt(k * k + 1), k);
+/// }
+///
+/// let mut n = 1u64;
+/// for _ in 0..10_000 {
+///     n = n.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+///     let root = primes::isqrt(n);
+///     // Compared in u128, where the square of one more than the root can't overflow.
+///     assert!((root as u128).pow(2) <= n as u128 && (root as u128 + 1).pow(2) > n as u128);
+///     assert_eq!(root, n.isqrt());
+/// }
+/// ```
+pub fn isqrt(n: u64) -> u64 {
+    if n < 2 {
+        return n;
+    }
+    // Newton's method decreases monotonically towards the root from any starting point above it.
+    let mut x = 1 << ((65 - n.leading_zeros()) / 2);
+    loop {
+        let y = (x + n / x) / 2;
+        if y >= x {
+            break;
+        }
+        x = y;
+    }
+    while x.checked_mul(x).is_none_or(|sq| sq > n) {
+        x -= 1;
+    }
+    while (x + 1).checked_mul(x + 1).is_some_and(|sq| sq <= n) {
+        x += 1;
+    }
+    x
+}
+
+/// Returns `true` if `p * p <= n`, without overflowing when `p` is `2^32` or more.
+#[inline(always)]
+pub(crate) fn square_at_most(p: u64, n: u64) -> bool {
+    p.checked_mul(p).is_some_and(|square| square <= n)
+}
+
+/// Returns `floor(sqrt(n))`, exactly for every `u128`, as [`isqrt`] does for a `u64`.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(primes::isqrt_u128(u128::MAX), u64::MAX as u128);
+/// assert_eq!(primes::isqrt_u128(1 << 100), 1 << 50);
+///
+/// for n in (0..1 << 16).chain((0..64).map(|k| u64::MAX as u128 >> k)) {
+///     assert_eq!(primes::isqrt_u128(n), primes::isqrt(n as u64) as u128);
+/// }
+///
+/// for k in (2..u64::MAX as u128).step_by(1 << 44).chain([u64::MAX as u128]) {
+///     assert_eq!(primes::isqrt_u128(k * k - 1), k - 1);
+///     assert_eq!(primes::isqrt_u128(k * k), k);
+///     assert_eq!(primes::isqrt_u128(k * k + 1), k);
+/// }
+/// ```
+pub fn isqrt_u128(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+    let mut x = 1 << ((129 - n.leading_zeros()) / 2);
+    loop {
+        let y = (x + n / x) / 2;
+        if y >= x {
+            break;
+        }
+        x = y;
+    }
+    while x.checked_mul(x).is_none_or(|sq| sq > n) {
+        x -= 1;
+    }
+    while (x + 1).checked_mul(x + 1).is_some_and(|sq| sq <= n) {
+        x += 1;
+    }
+    x
+}
+
+/// Returns `floor(n^(1/k))`, the largest `x` with `x^k <= n`, exactly for every `u64`.
+///
+/// Like [`isqrt`], Newton's method is run in integers from a power of two above the root,
+/// with checked powers standing in for any that overflow, as they are then far above `n`.
+/// Every `k` above 63 gives 1 for any positive `n`, as `2^64` doesn't fit.
+///
+/// # Panics
+///
+/// Panics if `k` is 0.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(primes::kth_root(1_000, 3), 10);
+/// assert_eq!(primes::kth_root(999, 3), 9);
+/// assert_eq!(primes::kth_root(12_345, 1), 12_345);
+/// assert_eq!(primes::kth_root(0, 5), 0);
+/// assert_eq!(primes::kth_root(u64::MAX, 2), primes::isqrt(u64::MAX));
+/// assert_eq!(primes::kth_root(u64::MAX, 3), 2_642_245);
+/// assert_eq!(primes::kth_root(u64::MAX, 63), 2);
+/// assert_eq!(primes::kth_root(u64::MAX, 64), 1);
+/// assert_eq!(primes::kth_root(1, u32::MAX), 1);
+///
+/// // Against a brute force search.
+/// for k in 1..=10 {
+///     let mut root = 0u64;
+///     for n in 0..1_000_000 {
+///         while (root + 1).pow(k) <= n {
+///             root += 1;
+///         }
+///         assert_eq!(primes::kth_root(n, k), root);
+///     }
+/// }
+///
+/// // Either side of exact powers, where the estimate is most often wrong.
+/// for k in 2..64 {
+///     let largest = primes::kth_root(u64::MAX, k);
+///     assert!(largest.checked_pow(k).is_some() && (largest + 1).checked_pow(k).is_none());
+///     for m in (2..=largest).step_by((largest as usize / 500).max(1)).chain([largest]) {
+///         let power = m.pow(k);
+///         assert_eq!(primes::kth_root(power - 1, k), m - 1);
+///         assert_eq!(primes::kth_root(power, k), m);
+///         assert_eq!(primes::kth_root(power + 1, k), m);
+///     }
+/// }
+/// ```
+///
+/// ```should_panic
+/// primes::kth_root(100, 0);
+/// ```
+pub fn kth_root(n: u64, k: u32) -> u64 {
+    assert!(k > 0, "the 0th root is undefined");
+    if k == 1 || n < 2 {
+        return n;
+    }
+    if k >= 64 {
+        return 1;
+    }
+    let bits = u64::BITS - n.leading_zeros();
+    let mut x = 1u64 << bits.div_ceil(k);
+    loop {
+        // Each step from above the root stays at or above it, until it can't decrease any further.
+        let quotient = x.checked_pow(k - 1).map_or(0, |power| n / power);
+        let next = (u64::from(k - 1) * x + quotient) / u64::from(k);
+        if next >= x {
+            return x;
+        }
+        x = next;
+    }
+}
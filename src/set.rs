@@ -0,0 +1,173 @@
+//! A sorted collection of primes for random access and queries, rather than streaming.
+
+use alloc::vec::Vec;
+
+use core::ops::{Bound, RangeBounds};
+
+use crate::below_vec;
+
+/// A sorted set of primes, answering membership, rank and neighbour queries by binary search.
+///
+/// Like [`nth`](crate::nth), [`PrimeSet::nth`] numbers the primes from 1, while [`as_slice`](PrimeSet::as_slice)
+/// indexes them from 0, so `set.nth(k)` is `set.as_slice()[k - 1]`.
+///
+/// Any values can be collected into a set, which sorts and deduplicates them unless they already are,
+/// but only primes are expected.
+///
+/// # Examples
+///
+/// ```
+/// use primes::PrimeSet;
+///
+/// let set = PrimeSet::below(100);
+/// assert_eq!(set.len(), 25);
+/// assert!(set.contains(97));
+/// assert!(!set.contains(91));
+/// assert_eq!(set.count_below(50), 15);
+/// assert_eq!(set.nth(1), Some(2));
+/// assert_eq!(set.nth(25), Some(97));
+/// assert_eq!(set.next_after(89), Some(97));
+/// assert_eq!(set.prev_before(89), Some(83));
+/// assert_eq!(set.range(10..20).collect::<Vec<_>>(), [11, 13, 17, 19]);
+///
+/// let collected: PrimeSet = [7, 3, 5, 3, 2].into_iter().collect();
+/// assert_eq!(collected.as_slice(), [2, 3, 5, 7]);
+/// ```
+///
+/// Every query agrees with the iterators, and with [`is_prime`](crate::is_prime), up to and past the largest prime:
+///
+/// ```
+/// use primes::PrimeSet;
+///
+/// let n = 1_000_000;
+/// let all = primes::below_vec(n);
+/// let set = PrimeSet::below(n);
+/// assert_eq!(set.as_slice(), all);
+/// assert_eq!(set, all.iter().copied().collect());
+/// assert_eq!(set.len(), 78_498);
+///
+/// let mut seed = 1u64;
+/// let queries = (0..300).map(|_| {
+///     seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+///     seed >> 44
+/// });
+/// for x in (0..100).chain(queries).chain(999_950..1_000_050).chain([u64::MAX - 1, u64::MAX]) {
+///     assert_eq!(set.contains(x), x <= n && primes::is_prime(x));
+///     let count = all.iter().filter(|&&p| p <= x).count();
+///     assert_eq!(set.count_below(x), count as u64);
+///     assert_eq!(set.next_after(x), all.iter().copied().find(|&p| p > x));
+///     assert_eq!(set.prev_before(x), all.iter().copied().rev().find(|&p| p < x));
+///     let window: Vec<_> = all.iter().copied().filter(|&p| x <= p && p - x < 1_000).collect();
+///     assert_eq!(set.range(x..x.saturating_add(1_000)).collect::<Vec<_>>(), window);
+/// }
+/// for k in (1..=78_498).step_by(101).chain([78_497, 78_498]) {
+///     assert_eq!(set.nth(k), primes::nth(k));
+///     assert_eq!(set.count_below(set.nth(k).unwrap()), k);
+/// }
+/// assert_eq!(set.nth(0), None);
+/// assert_eq!(set.nth(78_499), None);
+/// assert_eq!(set.next_after(999_983), None);
+/// assert_eq!(set.prev_before(2), None);
+/// assert_eq!(set.range(..).count(), 78_498);
+/// assert_eq!(set.range(..=2).collect::<Vec<_>>(), [2]);
+/// assert_eq!(set.range(999_983..).collect::<Vec<_>>(), [999_983]);
+///
+/// let empty = PrimeSet::below(1);
+/// assert!(empty.is_empty());
+/// assert_eq!((empty.nth(1), empty.next_after(0), empty.prev_before(u64::MAX)), (None, None, None));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PrimeSet {
+    primes: Vec<u64>,
+}
+
+impl PrimeSet {
+    /// Returns the set of primes less than or equal to `n`.
+    ///
+    /// # Panics
+    ///
+    /// Panics as [`below_vec`] does, if there are more primes than a `Vec` can hold.
+    pub fn below(n: u64) -> Self {
+        Self {
+            primes: below_vec(n),
+        }
+    }
+
+    /// Returns the number of primes in the set.
+    pub fn len(&self) -> usize {
+        self.primes.len()
+    }
+
+    /// Returns `true` if the set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.primes.is_empty()
+    }
+
+    /// Returns the primes in increasing order, indexed from 0.
+    pub fn as_slice(&self) -> &[u64] {
+        &self.primes
+    }
+
+    /// Returns an iterator over the primes, in increasing order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = u64> + ExactSizeIterator + '_ {
+        self.primes.iter().copied()
+    }
+
+    /// Returns `true` if `x` is in the set.
+    pub fn contains(&self, x: u64) -> bool {
+        self.primes.binary_search(&x).is_ok()
+    }
+
+    /// Returns the number of primes in the set less than or equal to `x`,
+    /// which is the 1-based index of `x` if it is in the set.
+    pub fn count_below(&self, x: u64) -> u64 {
+        self.primes.partition_point(|&p| p <= x) as u64
+    }
+
+    /// Returns the `k`th prime in the set, numbered from 1 as [`nth`](crate::nth) does, or `None` if there are fewer than `k`.
+    pub fn nth(&self, k: u64) -> Option<u64> {
+        let i = usize::try_from(k.checked_sub(1)?).ok()?;
+        self.primes.get(i).copied()
+    }
+
+    /// Returns the smallest prime in the set greater than `x`.
+    pub fn next_after(&self, x: u64) -> Option<u64> {
+        let i = self.primes.partition_point(|&p| p <= x);
+        self.primes.get(i).copied()
+    }
+
+    /// Returns the largest prime in the set less than `x`.
+    pub fn prev_before(&self, x: u64) -> Option<u64> {
+        let i = self.primes.partition_point(|&p| p < x);
+        Some(self.primes[i.checked_sub(1)?])
+    }
+
+    /// Returns an iterator over the primes in the set within `range`, in increasing order.
+    pub fn range<R: RangeBounds<u64>>(
+        &self,
+        range: R,
+    ) -> impl DoubleEndedIterator<Item = u64> + ExactSizeIterator + '_ {
+        let start = match range.start_bound() {
+            Bound::Included(&low) => self.primes.partition_point(|&p| p < low),
+            Bound::Excluded(&low) => self.primes.partition_point(|&p| p <= low),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&high) => self.primes.partition_point(|&p| p <= high),
+            Bound::Excluded(&high) => self.primes.partition_point(|&p| p < high),
+            Bound::Unbounded => self.primes.len(),
+        };
+        self.primes[start..end.max(start)].iter().copied()
+    }
+}
+
+impl FromIterator<u64> for PrimeSet {
+    fn from_iter<I: IntoIterator<Item = u64>>(iter: I) -> Self {
+        let mut primes: Vec<u64> = iter.into_iter().collect();
+        if !primes.is_sorted_by(|a, b| a < b) {
+            primes.sort_unstable();
+            primes.dedup();
+        }
+        Self { primes }
+    }
+}
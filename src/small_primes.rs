@@ -0,0 +1,44 @@
+//! A process-wide table of small primes, grown as needed and shared by factorizations.
+
+use alloc::sync::Arc;
+#[cfg(feature = "std")]
+use std::sync::RwLock;
+
+/// The largest prime the table grows to, so that it never takes more than about 4 MB.
+#[cfg(feature = "std")]
+const CAP: u64 = 1 << 24;
+
+/// The primes up to a bound, along with that bound.
+#[cfg(feature = "std")]
+static TABLE: RwLock<(u64, Option<Arc<[u32]>>)> = RwLock::new((0, None));
+
+/// Returns a table containing at least every prime up to `n`, in order,
+/// or `None` if `n` is beyond the largest table kept.
+#[cfg(feature = "std")]
+pub(crate) fn up_to(n: u64) -> Option<Arc<[u32]>> {
+    if n > CAP {
+        return None;
+    }
+    if let (covered, Some(primes)) = &*TABLE.read().unwrap_or_else(|e| e.into_inner()) {
+        if *covered >= n {
+            return Some(Arc::clone(primes));
+        }
+    }
+    let mut table = TABLE.write().unwrap_or_else(|e| e.into_inner());
+    // Another thread may have grown the table while this one waited for the lock.
+    if table.0 < n || table.1.is_none() {
+        // Grow geometrically, so that a run of increasing requests doesn't sieve too often.
+        let bound = n.max(table.0.saturating_mul(2)).clamp(1 << 10, CAP);
+        let primes: Arc<[u32]> = crate::up_to(bound)
+            .map(|p| u32::try_from(p).unwrap())
+            .collect();
+        *table = (bound, Some(primes));
+    }
+    table.1.clone()
+}
+
+/// Without `std` there is no lock to share a table behind, so none is kept, and factorizations sieve their own primes.
+#[cfg(not(feature = "std"))]
+pub(crate) fn up_to(_: u64) -> Option<Arc<[u32]>> {
+    None
+}
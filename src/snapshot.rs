@@ -0,0 +1,575 @@
+//! Saving where an iterator is up to, and resuming it later, in a small versioned binary format or through serde.
+
+use std::format;
+use std::io::{self, Read, Write};
+use std::vec::Vec;
+
+use crate::sieve::Wheeled;
+use crate::{segment_words, Backend, Builder, Primes, PrimesCount, WheelSize};
+
+/// Marks the start of a snapshot.
+const MAGIC: [u8; 6] = *b"PRIMES";
+/// Bumped whenever the layout changes.
+const VERSION: u8 = 1;
+/// The number of bytes after the magic bytes, version and length, and before the checksum:
+/// the wheel, the flags, and five `u64` fields.
+const BODY: u8 = 2 + 5 * 8;
+const HEADER: usize = MAGIC.len() + 2;
+const LEN: usize = HEADER + BODY as usize + 8;
+
+/// What a snapshot stores: the wheel, the flags, and the segment size in words, the limit, the count,
+/// how many primes have been yielded and the position.
+struct State {
+    wheel: u8,
+    flags: u8,
+    fields: [u64; 5],
+}
+
+const EXACT: u8 = 1;
+const AT_LIMIT: u8 = 2;
+const PEEKED: u8 = 4;
+
+/// Returns the 64-bit FNV-1a hash of `bytes`, which always changes if any one byte does.
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn invalid(reason: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid primes snapshot: {reason}"),
+    )
+}
+
+impl Primes {
+    /// Writes where the iterator is up to, so that [`load_state`](Primes::load_state) can resume it later,
+    /// for checkpointing a long scan to a file or a database.
+    ///
+    /// A snapshot is 58 bytes: the magic bytes `PRIMES`, a version byte, the length of the body,
+    /// then the wheel, or 2 for the sieve of Atkin, the segment size, the limit, the count, how many primes have been yielded and the position,
+    /// followed by a checksum of everything before it, with every integer little-endian.
+    /// Neither the segment nor the base primes are stored, as they are found again on loading,
+    /// which takes far less time than getting as far as the iterator did.
+    ///
+    /// The version is bumped whenever the layout changes, and only snapshots of the current version, 1, are read.
+    /// With the `serde` feature, the same fields can be stored with any serde format instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from writing to `w`, or [`InvalidInput`](io::ErrorKind::InvalidInput)
+    /// without writing anything if the iterator was built with a custom [`SegmentSource`](crate::SegmentSource),
+    /// which can't be stored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use primes::Primes;
+    ///
+    /// let mut primes = primes::below(1_000_000);
+    /// let before: Vec<_> = primes.by_ref().take(1_000).collect();
+    /// let mut snapshot = Vec::new();
+    /// primes.save_state(&mut snapshot)?;
+    /// assert_eq!(snapshot.len(), 58);
+    ///
+    /// let resumed = Primes::load_state(&snapshot[..])?;
+    /// assert_eq!((resumed.yielded(), resumed.progress()), (1_000, 7_919));
+    /// assert!(before.into_iter().chain(resumed).eq(primes::below(1_000_000)));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    ///
+    /// Resuming after any number of primes, with either wheel or the sieve of Atkin and small segments,
+    /// carries on exactly as the original iterator would have:
+    ///
+    /// ```
+    /// use primes::{Backend, Builder, Primes, WheelSize};
+    ///
+    /// let atkin = Builder::new().backend(Backend::Atkin);
+    /// for builder in [Builder::new().wheel(WheelSize::W30), Builder::new().wheel(WheelSize::W210), atkin] {
+    ///     let builder = builder.segment_size(64).limit(10_000);
+    ///     let all: Vec<_> = builder.build().collect();
+    ///     for k in 0..=all.len() {
+    ///         let mut primes = builder.build();
+    ///         let mut resumed: Vec<_> = primes.by_ref().take(k).collect();
+    ///         let mut snapshot = Vec::new();
+    ///         primes.save_state(&mut snapshot)?;
+    ///         resumed.extend(Primes::load_state(&snapshot[..])?);
+    ///         assert_eq!(resumed, all);
+    ///     }
+    /// }
+    ///
+    /// // Far from 0, where the base primes are filed into buckets, and after a peek.
+    /// let low = 1_000_000_000_000;
+    /// let builder = Builder::new().segment_size(64).limit(low + 200_000);
+    /// let all: Vec<_> = {
+    ///     let mut primes = builder.build();
+    ///     primes.skip_to(low);
+    ///     primes.collect()
+    /// };
+    /// for k in [0, 1, 2, 3, 50, 500, all.len() - 1, all.len()] {
+    ///     let mut primes = builder.build();
+    ///     primes.skip_to(low);
+    ///     let mut resumed: Vec<_> = primes.by_ref().take(k).collect();
+    ///     primes.peek();
+    ///     let mut snapshot = Vec::new();
+    ///     primes.save_state(&mut snapshot)?;
+    ///     let mut loaded = Primes::load_state(&snapshot[..])?;
+    ///     assert_eq!(loaded.peek(), primes.peek());
+    ///     assert_eq!((loaded.yielded(), loaded.remaining_count()), (primes.yielded(), primes.remaining_count()));
+    ///     resumed.extend(loaded);
+    ///     assert_eq!(resumed, all);
+    /// }
+    ///
+    /// // An exhausted iterator stays where it was, so can still have its limit raised.
+    /// let mut primes = primes::below(100);
+    /// primes.by_ref().for_each(drop);
+    /// let mut snapshot = Vec::new();
+    /// primes.save_state(&mut snapshot)?;
+    /// let mut loaded = Primes::load_state(&snapshot[..])?;
+    /// assert_eq!(loaded.next(), None);
+    /// loaded.extend_limit(120);
+    /// assert_eq!(loaded.collect::<Vec<_>>(), [101, 103, 107, 109, 113]);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn save_state(&self, mut w: impl Write) -> io::Result<()> {
+        let state = self.state().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "a custom segment source can't be saved",
+            )
+        })?;
+        let mut bytes = Vec::with_capacity(LEN);
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend([VERSION, BODY, state.wheel, state.flags]);
+        for field in state.fields {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+        bytes.extend_from_slice(&checksum(&bytes).to_le_bytes());
+        w.write_all(&bytes)
+    }
+
+    /// Resumes an iterator from a snapshot written by [`save_state`](Primes::save_state).
+    ///
+    /// The resumed iterator yields exactly what the saved one would have, and has the same
+    /// [`yielded`](Primes::yielded), [`limit`](Primes::limit) and [`remaining_count`](Primes::remaining_count).
+    /// If the saved iterator was built with shared [`BasePrimes`](crate::BasePrimes), the resumed one finds its own.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from reading `r`, which is [`UnexpectedEof`](io::ErrorKind::UnexpectedEof)
+    /// if the snapshot is truncated, and [`InvalidData`](io::ErrorKind::InvalidData) if it isn't a snapshot,
+    /// is from a later version, or has been corrupted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::ErrorKind;
+    ///
+    /// use primes::Primes;
+    ///
+    /// let mut primes = primes::below(1_000_000);
+    /// primes.nth(5_000);
+    /// let mut snapshot = Vec::new();
+    /// primes.save_state(&mut snapshot)?;
+    ///
+    /// // Changing any byte in any way is caught by the checksum, if not before.
+    /// for i in 0..snapshot.len() {
+    ///     for flip in [0x01, 0x10, 0x80, 0xff] {
+    ///         let mut corrupt = snapshot.clone();
+    ///         corrupt[i] ^= flip;
+    ///         let error = Primes::load_state(&corrupt[..]).unwrap_err();
+    ///         assert_eq!(error.kind(), ErrorKind::InvalidData);
+    ///     }
+    /// }
+    ///
+    /// for len in 0..snapshot.len() {
+    ///     let error = Primes::load_state(&snapshot[..len]).unwrap_err();
+    ///     assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+    /// }
+    ///
+    /// let error = Primes::load_state(&b"not a snapshot of anything"[..]).unwrap_err();
+    /// assert_eq!(error.kind(), ErrorKind::InvalidData);
+    ///
+    /// // A segment larger than any the limit would be built with is rejected before it is allocated,
+    /// // even with a valid checksum.
+    /// let mut huge = Vec::new();
+    /// primes::below(u64::MAX).save_state(&mut huge)?;
+    /// huge[10..18].copy_from_slice(&(1u64 << 40).to_le_bytes());
+    /// let fnv = huge[..50].iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, &byte| {
+    ///     (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    /// });
+    /// huge[50..].copy_from_slice(&fnv.to_le_bytes());
+    /// let error = Primes::load_state(&huge[..]).unwrap_err();
+    /// assert_eq!(error.kind(), ErrorKind::InvalidData);
+    /// assert_eq!(error.to_string(), "invalid primes snapshot: segment too large");
+    ///
+    /// assert_eq!(Primes::load_state(&snapshot[..])?.next(), primes.next());
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn load_state(mut r: impl Read) -> io::Result<Primes> {
+        let mut bytes = [0; LEN];
+        r.read_exact(&mut bytes[..HEADER])?;
+        if bytes[..MAGIC.len()] != MAGIC {
+            return Err(invalid("missing magic bytes"));
+        }
+        if bytes[MAGIC.len()] != VERSION {
+            return Err(invalid("unsupported version"));
+        }
+        if bytes[MAGIC.len() + 1] != BODY {
+            return Err(invalid("wrong length"));
+        }
+        r.read_exact(&mut bytes[HEADER..])?;
+        let (data, sum) = bytes.split_at(LEN - 8);
+        if checksum(data) != u64::from_le_bytes(sum.try_into().unwrap()) {
+            return Err(invalid("checksum mismatch"));
+        }
+        let mut fields = data[HEADER + 2..]
+            .chunks_exact(8)
+            .map(|field| u64::from_le_bytes(field.try_into().unwrap()));
+        let state = State {
+            wheel: data[HEADER],
+            flags: data[HEADER + 1],
+            fields: std::array::from_fn(|_| fields.next().unwrap()),
+        };
+        Primes::from_state(state).map_err(invalid)
+    }
+
+    /// Returns what a snapshot stores, or `None` for a custom segment source.
+    fn state(&self) -> Option<State> {
+        let wheel = match self.sieve {
+            Wheeled::W30(_) => 0,
+            Wheeled::W210(_) => 1,
+            Wheeled::Atkin(_) => 2,
+            Wheeled::Custom(_) => return None,
+        };
+        let mut flags = 0;
+        for (set, flag) in [
+            (self.exact, EXACT),
+            (self.at_limit, AT_LIMIT),
+            (self.peeked, PEEKED),
+        ] {
+            if set {
+                flags |= flag;
+            }
+        }
+        Some(State {
+            wheel,
+            flags,
+            fields: [
+                self.sieve.words() as u64,
+                self.limit,
+                self.count,
+                self.yielded,
+                self.p,
+            ],
+        })
+    }
+
+    /// Rebuilds the iterator a snapshot was taken of, or returns why the snapshot can't be of any iterator.
+    fn from_state(state: State) -> Result<Primes, &'static str> {
+        let (wheel, backend) = match state.wheel {
+            0 => (WheelSize::W30, Backend::Eratosthenes),
+            1 => (WheelSize::W210, Backend::Eratosthenes),
+            2 => (WheelSize::W30, Backend::Atkin),
+            _ => return Err("unknown wheel"),
+        };
+        let flags = state.flags;
+        if flags & !(EXACT | AT_LIMIT | PEEKED) != 0 {
+            return Err("unknown flags");
+        }
+        let [words, limit, count, yielded, p] = state.fields;
+
+        let peeked = flags & PEEKED != 0;
+        // Checked before building, as the segment is allocated up front.
+        let words = usize::try_from(words).map_err(|_| "segment too large")?;
+        if words > segment_words(usize::MAX, limit) {
+            return Err("segment too large");
+        }
+        if p == 0 || p > limit || peeked && (p == 1 || count == u64::MAX || yielded == 0) {
+            return Err("inconsistent position");
+        }
+        let mut primes = Builder::new()
+            .wheel(wheel)
+            .backend(backend)
+            .segment_size(words.saturating_mul(8))
+            .limit(limit)
+            .build();
+        if primes.sieve.words() != words {
+            return Err("segment size doesn't match the limit");
+        }
+        // Restored to just before the peeked prime, which is then found again.
+        primes.count = count + u64::from(peeked);
+        primes.yielded = yielded - u64::from(peeked);
+        primes.exact = flags & EXACT != 0;
+        primes.at_limit = flags & AT_LIMIT != 0;
+        let position = p - u64::from(peeked);
+        if primes.count > 0 && position < limit {
+            primes.move_to(position + 1);
+        } else {
+            primes.p = position;
+        }
+        if peeked && primes.peek() != Some(p) {
+            return Err("peeked value isn't the next prime");
+        }
+        Ok(primes)
+    }
+}
+
+impl PrimesCount {
+    /// Writes where the iterator is up to, as [`Primes::save_state`] does.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from writing to `w`, or [`InvalidInput`](io::ErrorKind::InvalidInput)
+    /// without writing anything if the iterator was built with a custom [`SegmentSource`](crate::SegmentSource),
+    /// which can't be stored.
+    pub fn save_state(&self, w: impl Write) -> io::Result<()> {
+        self.0.save_state(w)
+    }
+
+    /// Resumes an iterator from a snapshot written by [`PrimesCount::save_state`], as [`Primes::load_state`] does.
+    ///
+    /// # Errors
+    ///
+    /// As for [`Primes::load_state`], and also returns [`InvalidData`](io::ErrorKind::InvalidData)
+    /// if the snapshot is of an iterator that doesn't know exactly how many primes remain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use primes::{Primes, PrimesCount};
+    ///
+    /// let mut first = primes::first(100);
+    /// first.nth(9);
+    /// let mut snapshot = Vec::new();
+    /// first.save_state(&mut snapshot)?;
+    /// let resumed = PrimesCount::load_state(&snapshot[..])?;
+    /// assert_eq!(resumed.len(), 90);
+    /// assert!(resumed.eq(first));
+    ///
+    /// let mut snapshot = Vec::new();
+    /// primes::below(100).save_state(&mut snapshot)?;
+    /// assert!(PrimesCount::load_state(&snapshot[..]).is_err());
+    /// assert!(Primes::load_state(&snapshot[..]).is_ok());
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn load_state(r: impl Read) -> io::Result<PrimesCount> {
+        let primes = Primes::load_state(r)?;
+        if !primes.exact {
+            return Err(invalid("not of an iterator with an exact count"));
+        }
+        Ok(PrimesCount(primes))
+    }
+}
+
+/// The fields of a serialized [`Primes`], which are those of [`Primes::save_state`]'s snapshot, with names.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename = "Primes", deny_unknown_fields)]
+struct Fields {
+    version: u8,
+    wheel: u8,
+    flags: u8,
+    segment_words: u64,
+    limit: u64,
+    count: u64,
+    yielded: u64,
+    position: u64,
+}
+
+/// Stores where the iterator is up to, as [`Primes::save_state`] does, for checkpointing through serde.
+///
+/// Only available with the `serde` feature.
+///
+/// It is serialized as a struct named `Primes` with the fields of a snapshot:
+/// `version`, `wheel`, `flags`, `segment_words`, `limit`, `count`, `yielded` and `position`, all integers.
+/// The version is 1, and is bumped whenever what the fields mean changes, which also changes the binary snapshot's.
+/// As with a snapshot, neither the segment nor the base primes are stored, and they are found again on deserializing.
+///
+/// # Errors
+///
+/// Serializing fails if the iterator was built with a custom [`SegmentSource`](crate::SegmentSource).
+///
+/// # Examples
+///
+/// ```
+/// let mut primes = primes::below(1_000_000);
+/// primes.nth(999);
+/// let json = serde_json::to_string(&primes)?;
+/// assert_eq!(
+///     json,
+///     format!(
+///         r#"{{"version":1,"wheel":0,"flags":0,"segment_words":{},"limit":1000000,"count":{},"yielded":1000,"position":7919}}"#,
+///         primes::Builder::default_segment_size().div_ceil(8).min(1_000_000 / 240 + 1),
+///         u64::MAX - 1_000,
+///     ),
+/// );
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+#[cfg(feature = "serde")]
+impl serde::Serialize for Primes {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::Error;
+
+        let state = self
+            .state()
+            .ok_or_else(|| S::Error::custom("a custom segment source can't be saved"))?;
+        let [segment_words, limit, count, yielded, position] = state.fields;
+        Fields {
+            version: VERSION,
+            wheel: state.wheel,
+            flags: state.flags,
+            segment_words,
+            limit,
+            count,
+            yielded,
+            position,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Resumes an iterator from what its [`Serialize`](serde::Serialize) impl stored, as [`Primes::load_state`] does,
+/// so that it yields exactly what the serialized one would have.
+///
+/// Only available with the `serde` feature.
+///
+/// # Errors
+///
+/// Deserializing fails for anything that isn't of the current version,
+/// or isn't where any iterator could have been up to, such as a position past the limit.
+///
+/// # Examples
+///
+/// ```
+/// use primes::{Backend, Builder, Primes, WheelSize};
+///
+/// // Resuming after any number of primes, with either wheel or the sieve of Atkin and small segments.
+/// let atkin = Builder::new().backend(Backend::Atkin);
+/// for builder in [Builder::new().wheel(WheelSize::W30), Builder::new().wheel(WheelSize::W210), atkin] {
+///     let builder = builder.segment_size(64).limit(10_000);
+///     let all: Vec<_> = builder.build().collect();
+///     for k in 0..=all.len() {
+///         let mut primes = builder.build();
+///         let mut resumed: Vec<_> = primes.by_ref().take(k).collect();
+///         let json = serde_json::to_string(&primes)?;
+///         let loaded: Primes = serde_json::from_str(&json)?;
+///         assert_eq!((loaded.yielded(), loaded.limit()), (primes.yielded(), primes.limit()));
+///         resumed.extend(loaded);
+///         assert_eq!(resumed, all);
+///     }
+/// }
+///
+/// // The remaining count carries over too.
+/// let mut first = Builder::new().count(1_000).build();
+/// first.nth(499);
+/// let loaded: Primes = serde_json::from_str(&serde_json::to_string(&first)?)?;
+/// assert_eq!(loaded.remaining_count(), Some(500));
+/// assert!(loaded.eq(first));
+///
+/// let error = serde_json::from_str::<Primes>(
+///     r#"{"version":1,"wheel":0,"flags":0,"segment_words":1,"limit":100,"count":0,"yielded":0,"position":101}"#,
+/// )
+/// .unwrap_err();
+/// assert!(error.to_string().starts_with("invalid primes snapshot: inconsistent position"));
+/// let error = serde_json::from_str::<Primes>(
+///     r#"{"version":2,"wheel":0,"flags":0,"segment_words":1,"limit":100,"count":0,"yielded":0,"position":1}"#,
+/// )
+/// .unwrap_err();
+/// assert!(error.to_string().starts_with("invalid primes snapshot: unsupported version"));
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+///
+/// Resuming through serde and through [`Primes::load_state`] after the same number of primes gives the same iterator:
+///
+/// ```
+/// use primes::{Builder, Primes};
+///
+/// let builder = Builder::new().segment_size(64).limit(1_000_000);
+/// for k in [0, 1, 100, 1_000, 78_497, 78_498] {
+///     let mut primes = builder.build();
+///     primes.by_ref().take(k).for_each(drop);
+///     let mut snapshot = Vec::new();
+///     primes.save_state(&mut snapshot)?;
+///     let json = serde_json::to_string(&primes)?;
+///
+///     let loaded = Primes::load_state(&snapshot[..])?;
+///     let deserialized: Primes = serde_json::from_str(&json)?;
+///     assert_eq!((loaded.yielded(), loaded.remaining_count()), (deserialized.yielded(), deserialized.remaining_count()));
+///     assert!(loaded.eq(deserialized));
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Primes {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let fields = Fields::deserialize(deserializer)?;
+        if fields.version != VERSION {
+            return Err(D::Error::custom(
+                "invalid primes snapshot: unsupported version",
+            ));
+        }
+        let state = State {
+            wheel: fields.wheel,
+            flags: fields.flags,
+            fields: [
+                fields.segment_words,
+                fields.limit,
+                fields.count,
+                fields.yielded,
+                fields.position,
+            ],
+        };
+        Primes::from_state(state)
+            .map_err(|reason| D::Error::custom(format!("invalid primes snapshot: {reason}")))
+    }
+}
+
+/// Stores where the iterator is up to, as [`Primes`] does.
+///
+/// Only available with the `serde` feature.
+#[cfg(feature = "serde")]
+impl serde::Serialize for PrimesCount {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+/// Resumes an iterator as [`Primes`] does, also failing if it is of an iterator that doesn't know exactly how many primes remain.
+///
+/// Only available with the `serde` feature.
+///
+/// # Examples
+///
+/// ```
+/// use primes::{Primes, PrimesCount};
+///
+/// let mut first = primes::first(100);
+/// first.nth(9);
+/// let json = serde_json::to_string(&first)?;
+/// let resumed: PrimesCount = serde_json::from_str(&json)?;
+/// assert_eq!(resumed.len(), 90);
+/// assert!(resumed.eq(first));
+///
+/// let json = serde_json::to_string(&primes::below(100))?;
+/// assert!(serde_json::from_str::<PrimesCount>(&json).is_err());
+/// assert!(serde_json::from_str::<Primes>(&json).is_ok());
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for PrimesCount {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let primes = Primes::deserialize(deserializer)?;
+        if !primes.exact {
+            return Err(D::Error::custom(
+                "invalid primes snapshot: not of an iterator with an exact count",
+            ));
+        }
+        Ok(PrimesCount(primes))
+    }
+}
@@ -0,0 +1,188 @@
+//! Filling segments from a custom source, for trying out other ways of finding the primes in a range
+//! behind the same iterator.
+//!
+//! A custom source sets a flag for every value in the segment, which is read back into the mod 30 wheel's layout,
+//! so everything but the filling is shared with the sieve of Eratosthenes.
+//! The sieve's own backends never go through it, so it costs them nothing.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use crate::sieve::{as_index, Wheel, W30};
+use crate::{Backend, Builder, Primes};
+
+/// Something that can find the primes in each segment for [`Builder::build_with_source`].
+///
+/// It isn't sealed, as it is there to be implemented outside the crate, for experimenting with other algorithms,
+/// or with other hardware, while reusing everything [`Primes`] does on top of the segments.
+/// Check an implementation with [`assert_backend_correct`].
+///
+/// # Examples
+///
+/// A naive source, which tries dividing each value by the base primes:
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use primes::{Builder, SegmentSource};
+///
+/// struct TrialDivision;
+///
+/// impl SegmentSource for TrialDivision {
+///     fn fill(&self, start: u64, end: u64, base: &[u64], flags: &mut [u64]) {
+///         for n in start.max(2)..end {
+///             if base.iter().take_while(|&&p| p * p <= n).all(|&p| n % p != 0) {
+///                 let i = n - start;
+///                 flags[(i / 64) as usize] |= 1 << (i % 64);
+///             }
+///         }
+///     }
+/// }
+///
+/// primes::assert_backend_correct(TrialDivision, 20_000);
+///
+/// let mut primes = Builder::new().limit(1_000_000).build_with_source(Arc::new(TrialDivision));
+/// primes.skip_to(999_000);
+/// assert_eq!(primes.count(), 65);
+/// ```
+pub trait SegmentSource: Send + Sync {
+    /// Sets bit `i % 64` of `flags[i / 64]` for each `start + i` less than `end` that is prime.
+    ///
+    /// `start` is at least 1, and `flags` has just enough words for the values up to `end`, all clear.
+    /// `base` holds every prime whose square is less than `end`, in increasing order,
+    /// or only up to the square root of the iterator's limit if that is smaller, as no flag past the limit is ever read.
+    fn fill(&self, start: u64, end: u64, base: &[u64], flags: &mut [u64]);
+}
+
+/// Finds the primes in the segment with a separate iterator using the backend, which finds its own base primes,
+/// so that the built-in sieves can be compared with custom sources through the same path.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use primes::{Backend, Builder};
+///
+/// let primes = Builder::new().limit(100_000).build_with_source(Arc::new(Backend::Atkin));
+/// assert!(primes.eq(primes::below(100_000)));
+/// ```
+impl SegmentSource for Backend {
+    fn fill(&self, start: u64, end: u64, _base: &[u64], flags: &mut [u64]) {
+        let mut primes = Builder::new().backend(*self).limit(end - 1).build();
+        primes.skip_to(start);
+        for p in primes {
+            let i = p - start;
+            flags[as_index(i / 64)] |= 1 << (i % 64);
+        }
+    }
+}
+
+/// The mod 30 wheel, with segments filled by a custom source.
+#[derive(Clone)]
+pub(crate) struct Custom(pub(crate) Arc<dyn SegmentSource>);
+
+impl Wheel for Custom {
+    const PRIMES: &'static [u64] = W30::PRIMES;
+    const MODULUS: u64 = W30::MODULUS;
+    const SPOKES: usize = W30::SPOKES;
+    const RESIDUES: &'static [u64] = W30::RESIDUES;
+    const CEIL_INDEX: &'static [u64] = W30::CEIL_INDEX;
+    const GAPS: &'static [u64] = W30::GAPS;
+    const STEPS: &'static [u64] = W30::STEPS;
+    const PRESIEVED: &'static [u64] = W30::PRESIEVED;
+    const PATTERN: &'static [u64] = W30::PATTERN;
+    const MASKS: &'static [u64] = W30::MASKS;
+    const MASK_RANGES: &'static [(usize, usize); 64] = W30::MASK_RANGES;
+
+    fn source(&self) -> Option<&dyn SegmentSource> {
+        Some(&*self.0)
+    }
+}
+
+/// Panics unless iterators using `source` yield exactly the primes the sieve of Eratosthenes does, up to `limit`,
+/// however they are driven.
+///
+/// This runs the same checks the crate's own backends are held to: every way of consuming the iterator,
+/// peeking, skipping, raising the limit and taking whole segments, across segment sizes
+/// and limits either side of the squares of small primes and of segment boundaries, up to `limit`.
+/// Each check builds fresh iterators, so it takes a few hundred times as long as finding the primes below `limit` once.
+///
+/// # Panics
+///
+/// Panics if any check fails, naming the builder it failed for.
+///
+/// # Examples
+///
+/// ```
+/// primes::assert_backend_correct(primes::Backend::Eratosthenes, 100_000);
+/// ```
+///
+/// A source that misses a prime is caught:
+///
+/// ```should_panic
+/// use primes::SegmentSource;
+///
+/// struct Forgetful;
+///
+/// impl SegmentSource for Forgetful {
+///     fn fill(&self, start: u64, end: u64, base: &[u64], flags: &mut [u64]) {
+///         primes::Backend::Eratosthenes.fill(start, end, base, flags);
+///         if (start..end).contains(&1_009) {
+///             let i = 1_009 - start;
+///             flags[(i / 64) as usize] &= !(1 << (i % 64));
+///         }
+///     }
+/// }
+///
+/// primes::assert_backend_correct(Forgetful, 10_000);
+/// ```
+pub fn assert_backend_correct(source: impl SegmentSource + 'static, limit: u64) {
+    let source: Arc<dyn SegmentSource> = Arc::new(source);
+    let check = |builder: Builder, raised: u64| {
+        let custom = || builder.build_with_source(Arc::clone(&source));
+        let expected = || builder.build();
+        assert!(custom().eq(expected()), "the primes differ for {builder:?}");
+        assert_eq!(
+            custom().count(),
+            expected().count(),
+            "count for {builder:?}"
+        );
+        assert_eq!(custom().last(), expected().last(), "last for {builder:?}");
+        let xor = |primes: Primes| primes.fold(0, |sum, p| sum ^ p);
+        assert_eq!(xor(custom()), xor(expected()), "fold for {builder:?}");
+        let (mut c, mut e) = (custom(), expected());
+        assert_eq!(c.nth(100), e.nth(100), "nth for {builder:?}");
+        assert_eq!(c.peek(), e.peek(), "peek for {builder:?}");
+        let to = e.limit() / 2;
+        c.skip_to(to);
+        e.skip_to(to);
+        assert!(
+            c.by_ref().take(100).eq(e.by_ref().take(100)),
+            "skip_to({to}) for {builder:?}",
+        );
+        c.extend_limit(raised);
+        e.extend_limit(raised);
+        let (mut c_batch, mut e_batch) = (Vec::new(), Vec::new());
+        while e.next_segment(&mut e_batch) {
+            assert!(c.next_segment(&mut c_batch), "next_segment for {builder:?}");
+            assert_eq!(c_batch, e_batch, "next_segment for {builder:?}");
+        }
+        assert!(
+            !c.next_segment(&mut c_batch),
+            "next_segment for {builder:?}"
+        );
+    };
+    let limits = [
+        0, 1, 2, 7, 12, 13, 48, 49, 50, 120, 121, 168, 169, 239, 240, 241, 289, 3_720, 3_721, 4_489,
+    ];
+    for size in [8, 24, 1_000, 8_008] {
+        for n in limits.into_iter().filter(|&n| n <= limit) {
+            // Raised to a small multiple, so that every check doesn't go all the way to `limit`.
+            let raised = limit.min(n.max(500) * 2).max(n);
+            check(Builder::new().segment_size(size).limit(n), raised);
+        }
+        check(Builder::new().segment_size(size).limit(limit), limit);
+    }
+    check(Builder::new().limit(limit), limit);
+}
@@ -0,0 +1,108 @@
+//! Representations of integers as sums of two squares.
+
+use crate::modular::pow_mod;
+use crate::{divisors, is_prime, isqrt};
+
+/// Returns the number of ordered representations of `n` as `a^2 + b^2`, counting signs and zero.
+///
+/// e.g. `5 = (±1)^2 + (±2)^2 = (±2)^2 + (±1)^2` gives `r2(5) = 8`.
+///
+/// `0 = 0^2 + 0^2` is the only representation of `0`, so `r2(0) = 1`.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(primes::r2(1), 4);
+/// assert_eq!(primes::r2(2), 4);
+/// assert_eq!(primes::r2(3), 0);
+/// assert_eq!(primes::r2(5), 8);
+/// assert_eq!(primes::r2(25), 12);
+/// assert_eq!(primes::r2(325), 24);
+///
+/// let mut counts = vec![0; 10_000];
+/// for a in -100i64..=100 {
+///     for b in -100i64..=100 {
+///         if let Some(count) = counts.get_mut((a * a + b * b) as usize) {
+///             *count += 1;
+///         }
+///     }
+/// }
+/// for (n, &count) in counts.iter().enumerate() {
+///     assert_eq!(primes::r2(n as u64), count);
+/// }
+/// ```
+pub fn r2(n: u64) -> u64 {
+    if n == 0 {
+        return 1;
+    }
+    let mut count = 4;
+    for (prime, exponent) in divisors(n) {
+        match prime % 4 {
+            1 => count *= exponent + 1,
+            3 if exponent % 2 == 1 => return 0,
+            _ => (),
+        }
+    }
+    count
+}
+
+/// Returns `true` if `n` can be written as `a^2 + b^2` for integers `a` and `b`, or `false` otherwise.
+///
+/// # Examples
+///
+/// ```
+/// assert!(primes::is_sum_of_two_squares(0));
+/// assert!(primes::is_sum_of_two_squares(25));
+/// assert!(primes::is_sum_of_two_squares(18));
+/// assert!(!primes::is_sum_of_two_squares(3));
+/// assert!(!primes::is_sum_of_two_squares(21));
+/// ```
+pub fn is_sum_of_two_squares(n: u64) -> bool {
+    n == 0 || divisors(n).all(|(prime, exponent)| prime % 4 != 3 || exponent % 2 == 0)
+}
+
+/// Returns `(a, b)` with `a <= b` and `a^2 + b^2 = p`, if `p` is a prime that can be written that way.
+///
+/// Such a decomposition exists exactly when `p = 2` or `p % 4 == 1`, and is then unique.
+/// Returns `None` for every other input, including composites.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(primes::two_squares(2), Some((1, 1)));
+/// assert_eq!(primes::two_squares(5), Some((1, 2)));
+/// assert_eq!(primes::two_squares(13), Some((2, 3)));
+///
+/// assert_eq!(primes::two_squares(7), None);
+/// assert_eq!(primes::two_squares(25), None);
+///
+/// let (a, b) = primes::two_squares(1_000_033).unwrap();
+/// assert_eq!(a * a + b * b, 1_000_033);
+///
+/// for p in primes::below(100_000).filter(|p| p % 4 == 1) {
+///     let (a, b) = primes::two_squares(p).unwrap();
+///     assert!(a <= b);
+///     assert_eq!(a * a + b * b, p);
+/// }
+/// ```
+pub fn two_squares(p: u64) -> Option<(u64, u64)> {
+    if p == 2 {
+        return Some((1, 1));
+    }
+    if p % 4 != 1 || !is_prime(p) {
+        return None;
+    }
+    // c ^ ((p - 1) / 4) is a square root of -1 whenever c is a quadratic non-residue.
+    let half = (p - 1) / 2;
+    let non_residue = (2..).find(|&c| pow_mod(c, half, p) == p - 1)?;
+    let root = pow_mod(non_residue, half / 2, p);
+    // Cornacchia's algorithm: run the Euclidean algorithm on p and the root until below sqrt(p).
+    let limit = isqrt(p);
+    let (mut r0, mut r1) = (p, root);
+    while r1 > limit {
+        (r0, r1) = (r1, r0 % r1);
+    }
+    let a = r1;
+    let b = isqrt(p - a * a);
+    Some((a.min(b), a.max(b)))
+}
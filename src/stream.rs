@@ -0,0 +1,496 @@
+//! Writing primes to byte streams, as text or in compact binary formats, and reading the binary formats back.
+
+use std::format;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::string::{String, ToString};
+
+/// How [`write_primes`] writes each prime.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum OutputFormat {
+    /// One prime per line in decimal, each followed by `\n`.
+    #[default]
+    Text,
+    /// A header row `index,prime` followed by one row per prime, numbering them from 1 in the order written.
+    Csv,
+    /// One JSON object per line, `{"index":k,"prime":p}`, numbering the primes from 1 in the order written.
+    JsonLines,
+    /// Each prime as 8 little-endian bytes.
+    BinaryLe,
+    /// Each prime as its difference from the one before, or from 0 for the first,
+    /// as an unsigned LEB128 varint: 7 bits per byte, least significant first,
+    /// with the top bit set on every byte but the last.
+    /// Gaps between primes below `10^12` all fit in 2 bytes, and most in 1.
+    ///
+    /// The primes must be strictly increasing.
+    BinaryDelta,
+}
+
+/// How [`write_factorizations`] writes each factorization.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FactorizationFormat {
+    /// One line per number, `360 = 2^3 * 3^2 * 5`, with exponents of 1 left out, and `1 = 1` for 1.
+    #[default]
+    Text,
+    /// A header row `n,factors` followed by one row per number, with the factors as in [`Text`](Self::Text),
+    /// such as `360,2^3 * 3^2 * 5`, or nothing after the comma for 1.
+    Csv,
+    /// One JSON object per line, `{"n":360,"factors":[[2,3],[3,2],[5,1]]}`, as `[prime, exponent]` pairs.
+    JsonLines,
+}
+
+/// A binary format that [`read_primes`] can decode, as written by [`write_primes`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum BinaryFormat {
+    /// As written with [`OutputFormat::BinaryLe`].
+    Le,
+    /// As written with [`OutputFormat::BinaryDelta`].
+    Delta,
+}
+
+impl From<BinaryFormat> for OutputFormat {
+    fn from(format: BinaryFormat) -> Self {
+        match format {
+            BinaryFormat::Le => OutputFormat::BinaryLe,
+            BinaryFormat::Delta => OutputFormat::BinaryDelta,
+        }
+    }
+}
+
+/// Writes `primes` to `w` in the given format, returning how many were written.
+///
+/// Writes are buffered internally and flushed at the end, so `w` doesn't need to be buffered.
+/// Primes are only taken from the iterator as they are written, so an error from `w` is returned
+/// as soon as the buffer fails to flush, without generating the rest.
+///
+/// # Errors
+///
+/// Returns any error from writing to `w`, and [`InvalidInput`](io::ErrorKind::InvalidInput)
+/// for [`OutputFormat::BinaryDelta`] if the primes aren't strictly increasing.
+/// Everything before the error has been written.
+///
+/// # Examples
+///
+/// ```
+/// use primes::OutputFormat;
+///
+/// let mut text = Vec::new();
+/// assert_eq!(primes::write_primes(&mut text, primes::below(20), OutputFormat::Text)?, 8);
+/// assert_eq!(text, b"2\n3\n5\n7\n11\n13\n17\n19\n");
+///
+/// let mut csv = Vec::new();
+/// primes::write_primes(&mut csv, primes::below(10), OutputFormat::Csv)?;
+/// assert_eq!(csv, b"index,prime\n1,2\n2,3\n3,5\n4,7\n");
+///
+/// let mut json = Vec::new();
+/// primes::write_primes(&mut json, primes::below(5), OutputFormat::JsonLines)?;
+/// assert_eq!(json, b"{\"index\":1,\"prime\":2}\n{\"index\":2,\"prime\":3}\n{\"index\":3,\"prime\":5}\n");
+///
+/// let mut empty = Vec::new();
+/// assert_eq!(primes::write_primes(&mut empty, primes::below(1), OutputFormat::Text)?, 0);
+/// assert!(empty.is_empty());
+///
+/// let mut binary = Vec::new();
+/// primes::write_primes(&mut binary, primes::below(1_000_000), OutputFormat::BinaryLe)?;
+/// assert_eq!(binary.len(), 78_498 * 8);
+/// let decoded = binary.chunks_exact(8).map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()));
+/// assert!(decoded.eq(primes::below(1_000_000)));
+///
+/// let mut delta = Vec::new();
+/// let written = primes::write_primes(&mut delta, primes::below(1_000_000), OutputFormat::BinaryDelta)?;
+/// assert_eq!(written, 78_498);
+/// assert!(delta.len() < 78_498 * 2);
+/// let (mut value, mut gap, mut shift, mut decoded) = (0, 0, 0, Vec::new());
+/// for byte in delta {
+///     gap |= u64::from(byte & 0x7f) << shift;
+///     shift += 7;
+///     if byte < 0x80 {
+///         value += gap;
+///         decoded.push(value);
+///         (gap, shift) = (0, 0);
+///     }
+/// }
+/// assert_eq!(decoded, primes::below_vec(1_000_000));
+///
+/// // Large values and gaps take more bytes each.
+/// let mut delta = Vec::new();
+/// primes::write_primes(&mut delta, [127, 128, u64::MAX], OutputFormat::BinaryDelta)?;
+/// assert_eq!(delta.len(), 1 + 1 + 10);
+///
+/// let error = primes::write_primes(&mut delta, [5, 3], OutputFormat::BinaryDelta).unwrap_err();
+/// assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+///
+/// An error from the writer stops generating primes straight away:
+///
+/// ```
+/// use std::io::{self, Write};
+///
+/// use primes::OutputFormat;
+///
+/// struct Failing(usize);
+///
+/// impl Write for Failing {
+///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+///         if self.0 < buf.len() {
+///             return Err(io::Error::other("disk full"));
+///         }
+///         self.0 -= buf.len();
+///         Ok(buf.len())
+///     }
+///
+///     fn flush(&mut self) -> io::Result<()> {
+///         Ok(())
+///     }
+/// }
+///
+/// for format in [
+///     OutputFormat::Text,
+///     OutputFormat::Csv,
+///     OutputFormat::JsonLines,
+///     OutputFormat::BinaryLe,
+///     OutputFormat::BinaryDelta,
+/// ] {
+///     let mut generated = 0;
+///     let primes = primes::below(u64::MAX).inspect(|_| generated += 1);
+///     let error = primes::write_primes(Failing(100_000), primes, format).unwrap_err();
+///     assert_eq!(error.to_string(), "disk full");
+///     assert!(generated < 200_000);
+/// }
+///
+/// // Failing on the final flush is reported too.
+/// let error = primes::write_primes(Failing(10), primes::below(100), OutputFormat::Text).unwrap_err();
+/// assert_eq!(error.to_string(), "disk full");
+/// ```
+pub fn write_primes<W: Write>(
+    w: W,
+    primes: impl IntoIterator<Item = u64>,
+    format: OutputFormat,
+) -> io::Result<u64> {
+    let mut w = BufWriter::new(w);
+    let mut written = 0;
+    match format {
+        OutputFormat::Text => {
+            for p in primes {
+                writeln!(w, "{p}")?;
+                written += 1;
+            }
+        }
+        OutputFormat::Csv => {
+            w.write_all(b"index,prime\n")?;
+            for p in primes {
+                written += 1;
+                writeln!(w, "{written},{p}")?;
+            }
+        }
+        OutputFormat::JsonLines => {
+            for p in primes {
+                written += 1;
+                writeln!(w, "{{\"index\":{written},\"prime\":{p}}}")?;
+            }
+        }
+        OutputFormat::BinaryLe => {
+            for p in primes {
+                w.write_all(&p.to_le_bytes())?;
+                written += 1;
+            }
+        }
+        OutputFormat::BinaryDelta => {
+            let mut last = None;
+            for p in primes {
+                let gap = match last {
+                    Some(last) if p <= last => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("{p} is not greater than {last}"),
+                        ));
+                    }
+                    Some(last) => p - last,
+                    None => p,
+                };
+                write_varint(&mut w, gap)?;
+                last = Some(p);
+                written += 1;
+            }
+        }
+    }
+    w.flush()?;
+    Ok(written)
+}
+
+/// Writes each number in `factorizations` with its prime factorization to `w` in the given format,
+/// returning how many were written.
+///
+/// The factorizations are `(prime, exponent)` pairs, as [`divisors`](crate::divisors)
+/// and [`factorizations_below`](crate::factorizations_below) yield them, and are written in the order given.
+/// Writes are buffered as [`write_primes`] does.
+///
+/// # Errors
+///
+/// Returns any error from writing to `w`. Everything before the error has been written.
+///
+/// # Examples
+///
+/// ```
+/// use primes::FactorizationFormat;
+///
+/// let numbers = [1, 12, 97, 360];
+/// let factorizations = || numbers.into_iter().map(|n| (n, primes::divisors(n)));
+///
+/// let mut text = Vec::new();
+/// assert_eq!(primes::write_factorizations(&mut text, factorizations(), FactorizationFormat::Text)?, 4);
+/// assert_eq!(String::from_utf8(text).unwrap(), "1 = 1\n12 = 2^2 * 3\n97 = 97\n360 = 2^3 * 3^2 * 5\n");
+///
+/// let mut csv = Vec::new();
+/// primes::write_factorizations(&mut csv, factorizations(), FactorizationFormat::Csv)?;
+/// assert_eq!(String::from_utf8(csv).unwrap(), "n,factors\n1,\n12,2^2 * 3\n97,97\n360,2^3 * 3^2 * 5\n");
+///
+/// let mut json = Vec::new();
+/// primes::write_factorizations(&mut json, factorizations(), FactorizationFormat::JsonLines)?;
+/// assert_eq!(
+///     String::from_utf8(json).unwrap(),
+///     concat!(
+///         "{\"n\":1,\"factors\":[]}\n",
+///         "{\"n\":12,\"factors\":[[2,2],[3,1]]}\n",
+///         "{\"n\":97,\"factors\":[[97,1]]}\n",
+///         "{\"n\":360,\"factors\":[[2,3],[3,2],[5,1]]}\n",
+///     )
+/// );
+///
+/// // The factorizations from a sieve write the same.
+/// let (mut sieved, mut divided) = (Vec::new(), Vec::new());
+/// primes::write_factorizations(&mut sieved, primes::factorizations_below(1_000), FactorizationFormat::JsonLines)?;
+/// let factorizations = (2..1_000).map(|n| (n, primes::divisors(n)));
+/// primes::write_factorizations(&mut divided, factorizations, FactorizationFormat::JsonLines)?;
+/// assert_eq!(sieved, divided);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn write_factorizations<W: Write, F: IntoIterator<Item = (u64, u64)>>(
+    w: W,
+    factorizations: impl IntoIterator<Item = (u64, F)>,
+    format: FactorizationFormat,
+) -> io::Result<u64> {
+    let mut w = BufWriter::new(w);
+    let mut written = 0;
+    if format == FactorizationFormat::Csv {
+        w.write_all(b"n,factors\n")?;
+    }
+    for (n, factors) in factorizations {
+        match format {
+            FactorizationFormat::Text | FactorizationFormat::Csv => {
+                let mut factors = factors.into_iter().peekable();
+                if format == FactorizationFormat::Text {
+                    write!(w, "{n} = ")?;
+                    if factors.peek().is_none() {
+                        w.write_all(b"1")?;
+                    }
+                } else {
+                    write!(w, "{n},")?;
+                }
+                let mut separator = "";
+                for (p, k) in factors {
+                    w.write_all(separator.as_bytes())?;
+                    if k == 1 {
+                        write!(w, "{p}")?;
+                    } else {
+                        write!(w, "{p}^{k}")?;
+                    }
+                    separator = " * ";
+                }
+            }
+            FactorizationFormat::JsonLines => {
+                write!(w, "{{\"n\":{n},\"factors\":[")?;
+                let mut separator = "";
+                for (p, k) in factors {
+                    write!(w, "{separator}[{p},{k}]")?;
+                    separator = ",";
+                }
+                w.write_all(b"]}")?;
+            }
+        }
+        w.write_all(b"\n")?;
+        written += 1;
+    }
+    w.flush()?;
+    Ok(written)
+}
+
+/// Writes `x` as an unsigned LEB128 varint.
+fn write_varint(w: &mut impl Write, mut x: u64) -> io::Result<()> {
+    let mut bytes = [0; 10];
+    let mut len = 0;
+    loop {
+        let byte = u8::try_from(x & 0x7f).unwrap();
+        x >>= 7;
+        if x == 0 {
+            bytes[len] = byte;
+            len += 1;
+            break;
+        }
+        bytes[len] = byte | 0x80;
+        len += 1;
+    }
+    w.write_all(&bytes[..len])
+}
+
+/// Returns an iterator lazily decoding the primes in `r`, as written by [`write_primes`] in a binary format.
+///
+/// Reads are buffered internally, so `r` doesn't need to be buffered.
+/// The primes are checked to be strictly increasing as they are read.
+/// Anything wrong with the stream is yielded as an `Err` item, after which the iterator ends,
+/// so every prime before the problem is still yielded.
+///
+/// # Errors
+///
+/// Yields any error from reading `r`, [`UnexpectedEof`](io::ErrorKind::UnexpectedEof)
+/// if the stream ends partway through a prime, and [`InvalidData`](io::ErrorKind::InvalidData)
+/// if the primes aren't strictly increasing, or a varint doesn't fit in a `u64`.
+/// An empty stream yields nothing, as it is what writing no primes gives.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::ErrorKind;
+///
+/// use primes::BinaryFormat;
+///
+/// for format in [BinaryFormat::Le, BinaryFormat::Delta] {
+///     let mut bytes = Vec::new();
+///     primes::write_primes(&mut bytes, primes::below(1_000_000), format.into())?;
+///     let read: Vec<_> = primes::read_primes(&bytes[..], format).collect::<Result<_, _>>()?;
+///     assert_eq!(read, primes::below_vec(1_000_000));
+///
+///     assert_eq!(primes::read_primes(&[][..], format).count(), 0);
+///
+///     // Ending partway through a prime.
+///     bytes.push(0x80);
+///     let mut read = primes::read_primes(&bytes[..], format);
+///     assert!(read.by_ref().take(78_498).all(|p| p.is_ok()));
+///     assert_eq!(read.next().unwrap().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+///     assert!(read.next().is_none());
+/// }
+///
+/// // Out of order.
+/// let mut bytes = Vec::new();
+/// primes::write_primes(&mut bytes, [2, 3, 5, 7], primes::OutputFormat::BinaryLe)?;
+/// bytes[8..24].rotate_left(8);
+/// let read: Vec<_> = primes::read_primes(&bytes[..], BinaryFormat::Le).collect();
+/// assert_eq!(read[..2].iter().map(|p| p.as_ref().unwrap()).collect::<Vec<_>>(), [&2, &5]);
+/// assert_eq!(read[2].as_ref().unwrap_err().kind(), ErrorKind::InvalidData);
+/// assert_eq!(read.len(), 3);
+///
+/// // A repeated prime is a gap of 0.
+/// let read: Vec<_> = primes::read_primes(&[2, 1, 0, 2][..], BinaryFormat::Delta).collect();
+/// assert_eq!((read[0].as_ref().ok(), read[1].as_ref().ok()), (Some(&2), Some(&3)));
+/// assert_eq!(read[2].as_ref().unwrap_err().kind(), ErrorKind::InvalidData);
+///
+/// // Gaps too large for a u64, on their own or added to the prime before.
+/// let mut overlong = vec![0xff; 10];
+/// overlong.push(0x01);
+/// let error = primes::read_primes(&overlong[..], BinaryFormat::Delta).next().unwrap().unwrap_err();
+/// assert_eq!(error.kind(), ErrorKind::InvalidData);
+/// let mut bytes = Vec::new();
+/// primes::write_primes(&mut bytes, [u64::MAX - 1], primes::OutputFormat::BinaryDelta)?;
+/// bytes.push(2);
+/// let read: Vec<_> = primes::read_primes(&bytes[..], BinaryFormat::Delta).collect();
+/// assert_eq!(read[1].as_ref().unwrap_err().kind(), ErrorKind::InvalidData);
+///
+/// // Ending partway through the first varint.
+/// let error = primes::read_primes(&[0x80][..], BinaryFormat::Delta).next().unwrap().unwrap_err();
+/// assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn read_primes<R: Read>(r: R, format: BinaryFormat) -> impl Iterator<Item = io::Result<u64>> {
+    let mut r = BufReader::new(r);
+    let mut last: Option<u64> = None;
+    let mut done = false;
+    std::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+        let next = match format {
+            BinaryFormat::Le => read_le(&mut r),
+            BinaryFormat::Delta => read_varint(&mut r).and_then(|gap| match (gap, last) {
+                (None, _) => Ok(None),
+                (Some(gap), None) => Ok(Some(gap)),
+                (Some(gap), Some(last)) => match last.checked_add(gap) {
+                    Some(p) if gap > 0 => Ok(Some(p)),
+                    _ => Err(invalid_data(format!("gap {gap} after {last}"))),
+                },
+            }),
+        };
+        let next = next.and_then(|p| match (p, last) {
+            (Some(p), Some(last)) if p <= last => {
+                Err(invalid_data(format!("{p} is not greater than {last}")))
+            }
+            _ => Ok(p),
+        });
+        match next {
+            Ok(Some(p)) => {
+                last = Some(p);
+                Some(Ok(p))
+            }
+            Ok(None) => {
+                done = true;
+                None
+            }
+            Err(error) => {
+                done = true;
+                Some(Err(error))
+            }
+        }
+    })
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Reads a single byte, or returns `None` at the end of the stream.
+fn read_byte(r: &mut impl BufRead) -> io::Result<Option<u8>> {
+    loop {
+        match r.fill_buf() {
+            Ok([]) => return Ok(None),
+            Ok(&[byte, ..]) => {
+                r.consume(1);
+                return Ok(Some(byte));
+            }
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+/// Reads a little-endian `u64`, or returns `None` if the stream ends before it starts.
+fn read_le(r: &mut impl BufRead) -> io::Result<Option<u64>> {
+    let Some(first) = read_byte(r)? else {
+        return Ok(None);
+    };
+    let mut bytes = [first, 0, 0, 0, 0, 0, 0, 0];
+    r.read_exact(&mut bytes[1..])?;
+    Ok(Some(u64::from_le_bytes(bytes)))
+}
+
+/// Reads an unsigned LEB128 varint, or returns `None` if the stream ends before it starts.
+fn read_varint(r: &mut impl BufRead) -> io::Result<Option<u64>> {
+    let Some(mut byte) = read_byte(r)? else {
+        return Ok(None);
+    };
+    let mut x = 0;
+    let mut shift = 0;
+    loop {
+        let bits = u64::from(byte & 0x7f);
+        if shift >= 64 || bits << shift >> shift != bits {
+            return Err(invalid_data("varint too large for a u64".to_string()));
+        }
+        x |= bits << shift;
+        if byte < 0x80 {
+            return Ok(Some(x));
+        }
+        shift += 7;
+        byte = read_byte(r)?.ok_or(io::ErrorKind::UnexpectedEof)?;
+    }
+}
@@ -0,0 +1,86 @@
+//! Bindings for calling the crate from JavaScript with `wasm-bindgen`.
+//!
+//! Every `u64` crosses the boundary as a `BigInt`, in both directions, so values past `2^53` arrive intact,
+//! and a `Number` passed where a `BigInt` is expected throws a `TypeError` in JavaScript rather than losing precision.
+//!
+//! The module is built for the web with
+//!
+//! ```sh
+//! cargo rustc --lib --release --target wasm32-unknown-unknown --features wasm --crate-type cdylib
+//! wasm-bindgen --target web --out-dir examples/wasm/pkg target/wasm32-unknown-unknown/release/primes.wasm
+//! ```
+//!
+//! rather than listing `cdylib` in `Cargo.toml`, as that would need a panic handler and allocator
+//! in builds without `std`. `examples/wasm/index.html` is a page that calls each function.
+//! The tests in `tests/wasm.rs` run in Node with
+//! `cargo test --target wasm32-unknown-unknown --features wasm --test wasm`,
+//! which needs `wasm-bindgen-test-runner`, from the `wasm-bindgen-cli` crate of the same version as `wasm-bindgen`.
+
+use std::vec::Vec;
+
+use js_sys::{Array, BigInt};
+use wasm_bindgen::prelude::*;
+
+use crate::{below, divisors};
+
+/// Returns `true` if `n` is prime, as [`is_prime`](crate::is_prime) does.
+///
+/// # Examples
+///
+/// ```
+/// assert!(primes::wasm::is_prime(18_446_744_073_709_551_557));
+/// assert!(!primes::wasm::is_prime(1));
+/// ```
+#[wasm_bindgen]
+pub fn is_prime(n: u64) -> bool {
+    crate::is_prime(n)
+}
+
+/// Returns the `n`th prime, numbered from 1, as [`nth`](crate::nth) does,
+/// or `undefined` in JavaScript if there is none in a `u64`.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(primes::wasm::nth(100), Some(541));
+/// assert_eq!(primes::wasm::nth(0), None);
+/// ```
+#[wasm_bindgen]
+pub fn nth(n: u64) -> Option<u64> {
+    crate::nth(n)
+}
+
+/// Returns the first `max_len` primes less than or equal to `n`, or all of them if there are fewer,
+/// as a `BigUint64Array` in JavaScript.
+///
+/// The cap is there so that a large `n` from a page can't ask for more than the browser has memory for:
+/// the primes below `10^9` alone would take 400 MB.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(primes::wasm::primes_below(30, 100), [2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+/// assert_eq!(primes::wasm::primes_below(u64::MAX, 5), [2, 3, 5, 7, 11]);
+/// assert_eq!(primes::wasm::primes_below(30, 0), []);
+/// ```
+#[wasm_bindgen]
+pub fn primes_below(n: u64, max_len: usize) -> Vec<u64> {
+    below(n).take(max_len).collect()
+}
+
+/// Returns the prime factorization of `n` as [`divisors`](crate::divisors) yields it,
+/// as a JavaScript array of `[prime, exponent]` pairs, with each prime a `BigInt` and each exponent a `Number`,
+/// such as `[[2n, 3], [3n, 2], [5n, 1]]` for 360.
+///
+/// The array is empty for 0 and 1.
+/// Only works when built for WebAssembly, as it makes JavaScript values.
+#[wasm_bindgen]
+pub fn factorize(n: u64) -> JsValue {
+    divisors(n)
+        .map(|(p, e)| {
+            let e = u32::try_from(e).expect("exponents of a u64 are at most 63");
+            Array::of2(&BigInt::from(p).into(), &e.into())
+        })
+        .collect::<Array>()
+        .into()
+}
@@ -0,0 +1,335 @@
+//! Primes past `u64::MAX`, in `u128`.
+
+use alloc::vec;
+
+use crate::modular::WITNESSES;
+use crate::{is_prime, isqrt_u128, up_to};
+
+/// Bases for which Miller-Rabin is deterministic below `3.3 * 10^24`, about `2^81`.
+const WIDE_WITNESSES: [u128; 13] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41];
+
+/// Returns `true` if `n` is prime, or `false` otherwise, for a `u128`.
+///
+/// Values that fit in a `u64` are tested by [`is_prime`].
+/// Larger ones are tested by Miller-Rabin with the primes up to 41 as bases, in Montgomery form,
+/// which is proven correct below `3.3 * 10^24`, a little over `2^81`.
+/// Above that, a composite could in principle pass, but none is known to.
+///
+/// # Examples
+///
+/// ```
+/// assert!(primes::is_prime_u128(u64::MAX as u128 + 14));
+/// assert!(!primes::is_prime_u128(u64::MAX as u128 + 1));
+/// assert!(primes::is_prime_u128((1 << 89) - 1));
+/// assert!(primes::is_prime_u128((1 << 127) - 1));
+/// assert!(!primes::is_prime_u128((1 << 67) - 1));
+/// assert!(!primes::is_prime_u128(u128::MAX));
+///
+/// // A strong pseudoprime to every prime base up to 37.
+/// assert!(!primes::is_prime_u128(318_665_857_834_031_151_167_461));
+///
+/// // Products of two primes, and squares of primes, near 2^64 and 2^128.
+/// let p = 18_446_744_073_709_551_557u128;
+/// for q in [3, 65_537, 4_294_967_291, p - 2, p] {
+///     assert!(!primes::is_prime_u128(p * q));
+/// }
+///
+/// for n in (0..10_000).chain(u64::MAX as u128 - 1_000..=u64::MAX as u128) {
+///     assert_eq!(primes::is_prime_u128(n), primes::is_prime(n as u64));
+/// }
+/// ```
+pub fn is_prime_u128(n: u128) -> bool {
+    if let Ok(n) = u64::try_from(n) {
+        return is_prime(n);
+    }
+    if WITNESSES.iter().any(|&p| n.is_multiple_of(u128::from(p))) {
+        return false;
+    }
+    let montgomery = Montgomery::new(n);
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    let one = montgomery.one();
+    let minus_one = n - one;
+    'witness: for a in WIDE_WITNESSES {
+        let mut x = montgomery.pow(montgomery.to_form(a), d);
+        if x == one || x == minus_one {
+            continue;
+        }
+        for _ in 1..s {
+            x = montgomery.mul(x, x);
+            if x == minus_one {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+/// Returns an iterator over the primes greater than or equal to `low` and less than or equal to `high`, in `u128`.
+///
+/// The window is sieved all at once, by every prime up to `sqrt(high)`, with one bit for each odd number in it,
+/// so it takes `(high - low) / 16` bytes, and the base primes are streamed rather than stored.
+/// Finding the base primes dominates for narrow windows: it takes a few seconds up to `2^64`,
+/// and grows with `sqrt(high)`, so windows much beyond `2^80` are impractical.
+/// Each prime can be confirmed with [`is_prime_u128`] as well, for a check independent of the sieve.
+///
+/// # Panics
+///
+/// Panics if the window has more odd numbers than a `usize` can count.
+///
+/// # Examples
+///
+/// ```
+/// let window: Vec<_> = primes::between_u128(100, 130).collect();
+/// assert_eq!(window, [101, 103, 107, 109, 113, 127]);
+/// assert_eq!(primes::between_u128(0, 10).collect::<Vec<_>>(), [2, 3, 5, 7]);
+/// assert_eq!(primes::between_u128(10, 5).next(), None);
+///
+/// for (low, high) in [(0u64, 1_000_000u64), (999_000, 1_001_000), (1 << 40, (1 << 40) + 100_000)] {
+///     assert!(primes::between_u128(low.into(), high.into()).eq(primes::between(low, high).map(u128::from)));
+/// }
+///
+/// // Straddling 2^64.
+/// let (low, high) = (u64::MAX as u128 - 2_000, u64::MAX as u128 + 2_000);
+/// let expected: Vec<_> = (low..=high).filter(|&n| primes::is_prime_u128(n)).collect();
+/// assert_eq!(primes::between_u128(low, high).collect::<Vec<_>>(), expected);
+/// assert!(expected.contains(&(u64::MAX as u128 - 58)) && expected.contains(&(u64::MAX as u128 + 14)));
+/// ```
+pub fn between_u128(low: u128, high: u128) -> impl Iterator<Item = u128> {
+    let two = (low <= 2 && 2 <= high).then_some(2);
+    // The odd numbers from `start` are numbered from 0, and a set bit means composite.
+    let start = low.max(1) | 1;
+    let len = if start > high {
+        0
+    } else {
+        usize::try_from((high - start) / 2 + 1).expect("window too wide to sieve")
+    };
+    let mut composite = vec![0u64; len.div_ceil(64)];
+    if start == 1 && len > 0 {
+        composite[0] |= 1;
+    }
+    if len > 0 {
+        let root = u64::try_from(isqrt_u128(high)).unwrap_or(u64::MAX);
+        for p in up_to(root).skip(1) {
+            let p = u128::from(p);
+            // The first odd multiple of `p` that is at least `start`, and at least `p^2`, as smaller ones have smaller factors.
+            let mut multiple = (p * p).max(start.div_ceil(p) * p);
+            if multiple.is_multiple_of(2) {
+                multiple += p;
+            }
+            let Some(first) = multiple.checked_sub(start).map(|offset| offset / 2) else {
+                continue;
+            };
+            let step = usize::try_from(p).unwrap_or(usize::MAX);
+            let mut i = usize::try_from(first).unwrap_or(usize::MAX);
+            while i < len {
+                composite[i / 64] |= 1 << (i % 64);
+                i += step;
+            }
+        }
+    }
+    let odd = (0..len)
+        .filter(move |&i| composite[i / 64] & (1 << (i % 64)) == 0)
+        .map(move |i| start + 2 * i as u128);
+    two.into_iter().chain(odd)
+}
+
+/// The primes up to this are found by trial division, and any larger factors by Pollard's rho,
+/// which finds a factor `p` in about `sqrt(p)` steps, so overtakes trial division a little past it.
+const TRIAL: u64 = 1 << 10;
+
+/// Returns the smallest prime factor of `n`, for a `u128`, or `None` for 0 and 1, which have none.
+///
+/// The primes up to `2^10` are tried first, then whatever is left is split by Pollard's rho with Brent's cycle finding,
+/// in Montgomery form, until every part passes [`is_prime_u128`].
+/// Rho takes about `p^(1/2)` steps to split off a prime factor `p`, so this is fast as long as
+/// every prime factor but the largest is below about `2^50`, but slow for a product of two primes near `2^64`.
+/// Past `2^81`, the primality test is only probable, as [`is_prime_u128`] explains.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(primes::smallest_factor_u128(0), None);
+/// assert_eq!(primes::smallest_factor_u128(1), None);
+/// assert_eq!(primes::smallest_factor_u128(91), Some(7));
+/// assert_eq!(primes::smallest_factor_u128(u128::MAX), Some(3));
+/// assert_eq!(primes::smallest_factor_u128((1 << 127) - 1), Some((1 << 127) - 1));
+///
+/// // Past trial division, whichever factor rho happens to split off first.
+/// let (p, q, r) = (1_000_003u128, 999_999_937, 18_446_744_073_709_551_557);
+/// assert_eq!(primes::smallest_factor_u128(p * q), Some(p));
+/// assert_eq!(primes::smallest_factor_u128(q * p * p), Some(p));
+/// assert_eq!(primes::smallest_factor_u128(q * r), Some(q));
+/// assert_eq!(primes::smallest_factor_u128(r * 1_031 * q), Some(1_031));
+///
+/// for n in (2..3_000).chain(1_000_000_000_000..1_000_000_000_300) {
+///     let smallest = primes::divisors(n).next().map(|(p, _)| u128::from(p));
+///     assert_eq!(primes::smallest_factor_u128(u128::from(n)), smallest);
+/// }
+/// ```
+pub fn smallest_factor_u128(n: u128) -> Option<u128> {
+    if n < 2 {
+        return None;
+    }
+    for p in up_to(TRIAL).map(u128::from) {
+        if n.is_multiple_of(p) {
+            return Some(p);
+        }
+    }
+    Some(smallest_odd_factor(n))
+}
+
+/// Returns the smallest prime factor of `n`, which must have none up to [`TRIAL`].
+fn smallest_odd_factor(n: u128) -> u128 {
+    if n < u128::from(TRIAL * TRIAL) || is_prime_u128(n) {
+        return n;
+    }
+    let d = rho(n);
+    smallest_odd_factor(d).min(smallest_odd_factor(n / d))
+}
+
+/// Returns a factor of the odd composite `n` other than 1 and `n`,
+/// by Pollard's rho with Brent's cycle finding, trying `x^2 + c` for each `c` in turn until one splits `n`.
+///
+/// The differences are multiplied together so that only one gcd is taken for every [`BATCH`] steps,
+/// backtracking one step at a time if that batch overshoots to `n`.
+fn rho(n: u128) -> u128 {
+    const BATCH: u64 = 128;
+    let montgomery = Montgomery::new(n);
+    // Multiplying by 2^128 mod n preserves every gcd with n, so the values are left in Montgomery form throughout.
+    let mut c = 0;
+    loop {
+        c += 1;
+        let c = montgomery.to_form(c);
+        let f = |x| montgomery.add(montgomery.mul(x, x), c);
+        let (mut x, mut y, mut saved) = (0, montgomery.to_form(2), 0);
+        let (mut q, mut g) = (montgomery.one(), 1);
+        let mut length = 1;
+        while g == 1 {
+            x = y;
+            for _ in 0..length {
+                y = f(y);
+            }
+            let mut k = 0;
+            while k < length && g == 1 {
+                saved = y;
+                for _ in 0..BATCH.min(length - k) {
+                    y = f(y);
+                    q = montgomery.mul(q, x.abs_diff(y));
+                }
+                g = gcd(q, n);
+                k += BATCH;
+            }
+            length *= 2;
+        }
+        if g == n {
+            loop {
+                saved = f(saved);
+                g = gcd(x.abs_diff(saved), n);
+                if g != 1 {
+                    break;
+                }
+            }
+        }
+        if g != n {
+            return g;
+        }
+    }
+}
+
+/// Returns the greatest common divisor of `a` and `b`.
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Multiplication mod an odd `n` in Montgomery form, where `x` is held as `x * 2^128 mod n`,
+/// so that reducing a 256-bit product only takes multiplications and a shift.
+struct Montgomery {
+    n: u128,
+    /// `-1 / n mod 2^128`.
+    neg_inv: u128,
+    /// `2^256 mod n`, which converts into Montgomery form.
+    r2: u128,
+}
+
+impl Montgomery {
+    fn new(n: u128) -> Self {
+        // Newton's method doubles the number of correct low bits each step, starting from the 3 that `n` gets right.
+        let mut inv = n;
+        for _ in 0..6 {
+            inv = inv.wrapping_mul(2u128.wrapping_sub(n.wrapping_mul(inv)));
+        }
+        let mut montgomery = Montgomery {
+            n,
+            neg_inv: inv.wrapping_neg(),
+            r2: 0,
+        };
+        let mut r2 = montgomery.one();
+        for _ in 0..128 {
+            r2 = montgomery.add(r2, r2);
+        }
+        montgomery.r2 = r2;
+        montgomery
+    }
+
+    /// Returns 1 in Montgomery form, `2^128 mod n`.
+    fn one(&self) -> u128 {
+        self.n.wrapping_neg() % self.n
+    }
+
+    fn to_form(&self, x: u128) -> u128 {
+        self.mul(x % self.n, self.r2)
+    }
+
+    fn add(&self, a: u128, b: u128) -> u128 {
+        let (sum, overflow) = a.overflowing_add(b);
+        if overflow || sum >= self.n {
+            sum.wrapping_sub(self.n)
+        } else {
+            sum
+        }
+    }
+
+    fn mul(&self, a: u128, b: u128) -> u128 {
+        let (high, low) = mul_wide(a, b);
+        let m = low.wrapping_mul(self.neg_inv);
+        let (m_high, m_low) = mul_wide(m, self.n);
+        // `low + m_low` is 0 mod 2^128 by the choice of `m`, so only its carry is kept.
+        let carry = u128::from(low.overflowing_add(m_low).1);
+        let (sum, overflow) = high.overflowing_add(m_high);
+        let (sum, overflow_carry) = sum.overflowing_add(carry);
+        if overflow || overflow_carry || sum >= self.n {
+            sum.wrapping_sub(self.n)
+        } else {
+            sum
+        }
+    }
+
+    fn pow(&self, mut base: u128, mut exp: u128) -> u128 {
+        let mut result = self.one();
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = self.mul(result, base);
+            }
+            base = self.mul(base, base);
+            exp >>= 1;
+        }
+        result
+    }
+}
+
+/// Returns the high and low halves of the 256-bit product `a * b`.
+fn mul_wide(a: u128, b: u128) -> (u128, u128) {
+    const LOW: u128 = u64::MAX as u128;
+    let (a1, a0) = (a >> 64, a & LOW);
+    let (b1, b0) = (b >> 64, b & LOW);
+    let (p00, p01, p10, p11) = (a0 * b0, a0 * b1, a1 * b0, a1 * b1);
+    let middle = (p00 >> 64) + (p01 & LOW) + (p10 & LOW);
+    let low = (p00 & LOW) | (middle << 64);
+    let high = p11 + (p01 >> 64) + (p10 >> 64) + (middle >> 64);
+    (high, low)
+}
@@ -0,0 +1,259 @@
+//! Runs the `primes` binary as a user would.
+
+#![cfg(feature = "std")]
+
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Output, Stdio};
+use std::time::{Duration, Instant};
+use std::{env, fs, process};
+
+use primes::BinaryFormat;
+
+fn primes(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_primes"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout_bytes(args: &[&str]) -> Vec<u8> {
+    let output = primes(args);
+    assert!(output.status.success(), "{args:?} failed: {output:?}");
+    assert!(output.stderr.is_empty());
+    output.stdout
+}
+
+fn stdout(args: &[&str]) -> String {
+    String::from_utf8(stdout_bytes(args)).unwrap()
+}
+
+#[test]
+fn nth() {
+    assert_eq!(stdout(&["nth", "1"]), "2\n");
+    assert_eq!(stdout(&["nth", "100"]), "541\n");
+    assert_eq!(stdout(&["nth", "1_000_000"]), "15485863\n");
+}
+
+#[test]
+fn list() {
+    assert_eq!(
+        stdout(&["list", "--below", "30"]),
+        "2\n3\n5\n7\n11\n13\n17\n19\n23\n29\n"
+    );
+    assert_eq!(stdout(&["list", "--below", "1"]), "");
+    assert_eq!(stdout(&["list", "--first", "5"]), "2\n3\n5\n7\n11\n");
+    assert_eq!(stdout(&["list", "--first", "0"]), "");
+
+    let listed: Vec<u64> = stdout(&["list", "--below", "1000000"])
+        .lines()
+        .map(|line| line.parse().unwrap())
+        .collect();
+    assert_eq!(listed, primes::below_vec(1_000_000));
+}
+
+#[test]
+fn factor() {
+    assert_eq!(stdout(&["factor", "360"]), "360 = 2^3 * 3^2 * 5\n");
+    assert_eq!(stdout(&["factor", "97"]), "97 = 97\n");
+    assert_eq!(stdout(&["factor", "1"]), "1 = 1\n");
+    assert_eq!(
+        stdout(&["factor", "18446744073709551615"]),
+        "18446744073709551615 = 3 * 5 * 17 * 257 * 641 * 65537 * 6700417\n"
+    );
+}
+
+#[test]
+fn isprime() {
+    assert_eq!(stdout(&["isprime", "7"]), "7 is prime\n");
+    assert_eq!(
+        stdout(&["isprime", "0", "1", "2", "91", "18446744073709551557"]),
+        "0 is not prime\n1 is not prime\n2 is prime\n91 is not prime\n18446744073709551557 is prime\n"
+    );
+}
+
+#[test]
+fn count() {
+    assert_eq!(stdout(&["count", "--below", "1"]), "0\n");
+    assert_eq!(stdout(&["count", "--below", "10000000"]), "664579\n");
+}
+
+#[test]
+fn digest() {
+    assert_eq!(
+        stdout(&["digest", "--below", "1000000"]),
+        "00000000000132a2-00000008be2da9e7-0000000000027893-00000000000f422f-15edc1c9d1fec373\n"
+    );
+}
+
+#[test]
+fn help() {
+    assert!(stdout(&["help"]).starts_with("usage: primes"));
+    assert_eq!(stdout(&["--help"]), stdout(&["help"]));
+}
+
+#[test]
+fn bad_input() {
+    for (args, code) in [
+        (&[][..], 2),
+        (&["frobnicate"], 2),
+        (&["nth"], 2),
+        (&["nth", "-3"], 2),
+        (&["nth", "x"], 2),
+        (&["nth", "18446744073709551616"], 2),
+        (&["nth", "1", "2"], 2),
+        (&["list", "--below"], 2),
+        (&["list", "--above", "10"], 2),
+        (&["isprime"], 2),
+        (&["isprime", "3", "four"], 2),
+        (&["count", "10"], 2),
+        (&["digest", "--first", "10"], 2),
+        (&["list", "--below", "10", "--first", "5"], 2),
+        (&["list", "--below", "10", "--format", "xml"], 2),
+        (&["list", "--below", "10", "--format"], 2),
+        (&["list", "--below", "10", "--below", "20"], 2),
+        (&["list", "10"], 2),
+        (&["factor"], 2),
+        (&["factor", "12", "--format", "binary"], 2),
+        (&["factor", "12", "0"], 1),
+        (
+            &[
+                "list",
+                "--below",
+                "10",
+                "--output",
+                "/nonexistent/primes.txt",
+            ],
+            1,
+        ),
+        (&["nth", "0"], 1),
+        (&["nth", "18446744073709551615"], 1),
+        (&["factor", "0"], 1),
+    ] {
+        let output = primes(args);
+        assert_eq!(output.status.code(), Some(code), "{args:?}");
+        assert!(output.stdout.is_empty(), "{args:?}");
+        assert!(String::from_utf8(output.stderr)
+            .unwrap()
+            .starts_with("error: "));
+    }
+}
+
+#[test]
+fn list_streams() {
+    // Far more primes than could be collected first, so the first lines only arrive if the output is streamed.
+    let mut child = Command::new(env!("CARGO_BIN_EXE_primes"))
+        .args(["list", "--first", "1000000000000"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+    let first: Vec<u64> = lines
+        .by_ref()
+        .take(100_000)
+        .map(|line| line.unwrap().parse().unwrap())
+        .collect();
+    assert_eq!(first, primes::first_vec(100_000));
+
+    // Closing the pipe early, as `head` does, ends it quietly.
+    drop(lines);
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn formats() {
+    for (format, golden) in [
+        ("lines", &include_bytes!("golden/below_30.lines")[..]),
+        ("csv", include_bytes!("golden/below_30.csv")),
+        ("jsonl", include_bytes!("golden/below_30.jsonl")),
+        ("binary", include_bytes!("golden/below_30.binary")),
+        ("delta", include_bytes!("golden/below_30.delta")),
+    ] {
+        assert_eq!(
+            stdout_bytes(&["list", "--below", "30", "--format", format]),
+            golden
+        );
+        assert_eq!(
+            stdout_bytes(&["list", "--first", "10", "--format", format]),
+            golden
+        );
+    }
+    assert_eq!(
+        stdout(&["list", "--format", "lines", "--below", "30"]),
+        stdout(&["list", "--below", "30"])
+    );
+
+    let factor = [
+        "factor",
+        "1",
+        "2",
+        "12",
+        "97",
+        "360",
+        "1001",
+        "18446744073709551615",
+    ];
+    for (format, golden) in [
+        ("lines", &include_bytes!("golden/factor.lines")[..]),
+        ("csv", include_bytes!("golden/factor.csv")),
+        ("jsonl", include_bytes!("golden/factor.jsonl")),
+    ] {
+        assert_eq!(
+            stdout_bytes(&[&factor[..], &["--format", format]].concat()),
+            golden
+        );
+    }
+}
+
+#[test]
+fn output_file() {
+    let path = env::temp_dir().join(format!("primes-cli-test-{}.csv", process::id()));
+    let path_str = path.to_str().unwrap();
+    assert_eq!(
+        stdout(&["list", "--below", "1000", "--format", "csv", "--output", path_str]),
+        ""
+    );
+    assert_eq!(
+        fs::read(&path).unwrap(),
+        stdout_bytes(&["list", "--below", "1000", "--format", "csv"])
+    );
+    assert_eq!(stdout(&["factor", "360", "--output", path_str]), "");
+    assert_eq!(fs::read_to_string(&path).unwrap(), "360 = 2^3 * 3^2 * 5\n");
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn binary_round_trip() {
+    for (name, format) in [("binary", BinaryFormat::Le), ("delta", BinaryFormat::Delta)] {
+        let bytes = stdout_bytes(&["list", "--below", "1000000", "--format", name]);
+        let read: Vec<u64> = primes::read_primes(&bytes[..], format)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(read, primes::below_vec(1_000_000));
+    }
+}
+
+#[test]
+fn binary_size_and_time() {
+    let timed = |format| {
+        let now = Instant::now();
+        let bytes = stdout_bytes(&["list", "--below", "10000000", "--format", format]);
+        (bytes.len(), now.elapsed())
+    };
+    let (lines, lines_time) = timed("lines");
+    let (binary, binary_time) = timed("binary");
+    let (delta, _) = timed("delta");
+    assert_eq!(binary, 664_579 * 8);
+    let digits: usize = primes::below(10_000_000)
+        .map(|p| p.to_string().len() + 1)
+        .sum();
+    assert_eq!(lines, digits);
+    assert!(delta < lines / 6);
+    // Writing bytes as they are is never much slower than formatting them as text, allowing for noise.
+    assert!(
+        binary_time < lines_time * 2 + Duration::from_millis(100),
+        "{binary_time:?} vs {lines_time:?}"
+    );
+}
@@ -0,0 +1,76 @@
+//! The `wasm-bindgen` bindings, run in Node with
+//! `cargo test --target wasm32-unknown-unknown --features wasm --test wasm`.
+
+#![cfg(all(feature = "wasm", target_arch = "wasm32"))]
+
+use js_sys::{Array, BigInt};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use primes::wasm::{factorize, is_prime, nth, primes_below};
+
+/// The largest prime below `2^64`, far past where a `Number` loses precision.
+const LARGEST: u64 = 18_446_744_073_709_551_557;
+
+#[wasm_bindgen_test]
+fn is_prime_past_2_53() {
+    assert!(is_prime(LARGEST));
+    assert!(!is_prime(LARGEST - 2));
+    assert!(is_prime((1 << 61) - 1));
+    assert!(!is_prime((1 << 53) + 1));
+}
+
+#[wasm_bindgen_test]
+fn nth_to_and_from_big_int() {
+    assert_eq!(nth(1), Some(2));
+    assert_eq!(nth(0), None);
+    assert_eq!(nth(1_000_000), Some(15_485_863));
+    assert_eq!(nth(u64::MAX), None);
+}
+
+#[wasm_bindgen_test]
+fn primes_below_is_capped() {
+    assert_eq!(primes_below(30, 100), [2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+    assert_eq!(primes_below(u64::MAX, 3), [2, 3, 5]);
+    assert_eq!(primes_below(1_000_000, usize::MAX).len(), 78_498);
+}
+
+#[wasm_bindgen_test]
+fn factorize_makes_big_int_pairs() {
+    let pairs = |n| -> Vec<(u64, u32)> {
+        Array::from(&factorize(n))
+            .iter()
+            .map(|pair| {
+                let pair = Array::from(&pair);
+                let p = u64::try_from(pair.get(0)).unwrap();
+                let e = pair.get(1).as_f64().unwrap();
+                (p, e as u32)
+            })
+            .collect()
+    };
+    assert_eq!(pairs(360), [(2, 3), (3, 2), (5, 1)]);
+    assert_eq!(pairs(1), []);
+    assert_eq!(pairs(0), []);
+    assert_eq!(pairs(LARGEST), [(LARGEST, 1)]);
+    assert_eq!(
+        pairs(u64::MAX),
+        [
+            (3, 1),
+            (5, 1),
+            (17, 1),
+            (257, 1),
+            (641, 1),
+            (65_537, 1),
+            (6_700_417, 1)
+        ]
+    );
+    assert_eq!(pairs(1 << 63), [(2, 63)]);
+
+    // The primes really are BigInts in JavaScript, not Numbers.
+    let first = Array::from(&factorize(LARGEST)).get(0);
+    assert!(Array::from(&first).get(0).is_bigint());
+    assert_eq!(
+        Array::from(&first).get(0),
+        JsValue::from(BigInt::from(LARGEST))
+    );
+}